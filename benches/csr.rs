@@ -0,0 +1,89 @@
+//! Compares whole-graph work on the live `Graph` against a frozen `CsrSnapshot`.
+//!
+//! Run with `cargo bench --bench csr`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use minerva::graph::{Graph, Topology, analytics};
+
+const NODES: usize = 100_000;
+const EDGES_PER_NODE: usize = 8;
+const RUNS: u32 = 5;
+
+fn build_graph() -> Graph {
+    let mut graph = Graph::new();
+    for i in 0..NODES {
+        let label = if i % 2 == 0 { "author" } else { "book" };
+        graph
+            .add_node(vec![label.to_string()], HashMap::new())
+            .expect("no schema is defined");
+    }
+
+    // Small LCG so every run sees the same graph without pulling in a rand dependency.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for from in 0..NODES {
+        for _ in 0..EDGES_PER_NODE {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let to = (state >> 33) as usize % NODES;
+            graph
+                .add_edge(from, to, "wrote".to_string())
+                .expect("both ends exist");
+        }
+    }
+
+    graph
+}
+
+fn time<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    start.elapsed() / RUNS
+}
+
+fn compare(name: &str, live: Duration, snapshot: Duration) {
+    println!(
+        "{name:<22} live {:>10.2?}   csr {:>10.2?}   speed-up {:>5.2}x",
+        live,
+        snapshot,
+        live.as_secs_f64() / snapshot.as_secs_f64()
+    );
+}
+
+fn main() {
+    let graph = build_graph();
+    let build = time(|| graph.snapshot());
+    let snapshot = graph.snapshot();
+
+    println!(
+        "{NODES} nodes, {} edges, snapshot built in {build:.2?}",
+        snapshot.edge_count()
+    );
+
+    compare("bfs", time(|| graph.bfs(0)), time(|| snapshot.bfs(0)));
+    compare(
+        "dfs (miss)",
+        time(|| graph.dfs(0, usize::MAX)),
+        time(|| snapshot.dfs(0, usize::MAX)),
+    );
+    compare(
+        "connected components",
+        time(|| analytics::connected_components(&graph)),
+        time(|| analytics::connected_components(&snapshot)),
+    );
+    compare(
+        "pagerank (10 iter)",
+        time(|| analytics::pagerank(&graph, 0.85, 10)),
+        time(|| analytics::pagerank(&snapshot, 0.85, 10)),
+    );
+    compare(
+        "triangle count",
+        time(|| analytics::triangle_count(&graph)),
+        time(|| analytics::triangle_count(&snapshot)),
+    );
+}
@@ -0,0 +1,113 @@
+//! Heap usage of interned labels and property keys compared to storing them inline.
+//!
+//! Run with `cargo bench --bench interning`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use minerva::graph::{Graph, Value};
+
+const NODES: usize = 1_000_000;
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Node and edge layout from before interning, every string owned by its node or edge.
+#[allow(dead_code)]
+struct InlineNode {
+    id: usize,
+    labels: Vec<String>,
+    properties: HashMap<String, Value>,
+}
+
+#[allow(dead_code)]
+struct InlineEdge {
+    from: usize,
+    to: usize,
+    label: String,
+}
+
+fn properties(i: usize) -> HashMap<String, Value> {
+    HashMap::from([
+        ("title".to_string(), Value::String(format!("Book {i}"))),
+        ("pages".to_string(), Value::Int(200 + i as i64 % 300)),
+    ])
+}
+
+fn measure<T>(build: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let value = build();
+    let used = ALLOCATED.load(Ordering::Relaxed) - before;
+    drop(black_box(value));
+    used
+}
+
+fn main() {
+    let inline = measure(|| {
+        let mut nodes = HashMap::new();
+        let mut edges: HashMap<usize, Vec<InlineEdge>> = HashMap::new();
+        for id in 0..NODES {
+            nodes.insert(
+                id,
+                InlineNode {
+                    id,
+                    labels: vec!["book".to_string()],
+                    properties: properties(id),
+                },
+            );
+            if id > 0 {
+                edges.entry(id - 1).or_default().push(InlineEdge {
+                    from: id - 1,
+                    to: id,
+                    label: "sequel".to_string(),
+                });
+            }
+        }
+        (nodes, edges)
+    });
+
+    let interned = measure(|| {
+        let mut graph = Graph::new();
+        for id in 0..NODES {
+            graph
+                .add_node(vec!["book".to_string()], properties(id))
+                .expect("no schema is defined");
+            if id > 0 {
+                graph
+                    .add_edge(id - 1, id, "sequel".to_string())
+                    .expect("both ends exist");
+            }
+        }
+        graph
+    });
+
+    let mib = |bytes: f64| bytes / (1024.0 * 1024.0);
+    // negative if interning ever ends up costing more
+    let saved = inline as f64 - interned as f64;
+    println!("{NODES} `book` nodes with two properties and one `sequel` edge each");
+    println!("inline strings   {:>8.1} MiB", mib(inline as f64));
+    println!("interned symbols {:>8.1} MiB", mib(interned as f64));
+    println!(
+        "saved            {:>8.1} MiB ({:.0}%)",
+        mib(saved),
+        100.0 * saved / inline as f64
+    );
+}
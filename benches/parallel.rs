@@ -0,0 +1,116 @@
+//! Compares the single-threaded analytics on a `CsrSnapshot` with `ParallelAnalytics` at
+//! increasing thread counts, and checks that every thread count produces the same result.
+//!
+//! Run with `cargo bench --bench parallel`.
+
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use minerva::graph::analytics::{self, parallel::ParallelAnalytics};
+use minerva::graph::{CsrSnapshot, Graph};
+
+const NODES: usize = 200_000;
+const EDGES_PER_NODE: usize = 8;
+const RUNS: u32 = 3;
+
+fn build_snapshot() -> CsrSnapshot {
+    let mut graph = Graph::new();
+    for _ in 0..NODES {
+        graph
+            .add_node(vec!["page".to_string()], HashMap::new())
+            .expect("no schema is defined");
+    }
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for from in 0..NODES {
+        for _ in 0..EDGES_PER_NODE {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let to = (state >> 33) as usize % NODES;
+            graph
+                .add_edge(from, to, "links".to_string())
+                .expect("both ends exist");
+        }
+    }
+
+    graph.snapshot()
+}
+
+fn time<T>(f: impl Fn() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let csr = build_snapshot();
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut thread_counts = vec![1, 2, 4, 8, cores];
+    thread_counts.retain(|&threads| threads <= cores.max(4));
+    thread_counts.sort_unstable();
+    thread_counts.dedup();
+
+    println!(
+        "{NODES} nodes, {} edges, {cores} cores available",
+        csr.edge_count()
+    );
+
+    let single = [
+        time(|| analytics::connected_components(&csr)),
+        time(|| analytics::pagerank(&csr, 0.85, 10)),
+        time(|| analytics::triangle_count(&csr)),
+    ];
+    println!(
+        "{:<12} components {:>10.2?}   pagerank {:>10.2?}   triangles {:>10.2?}",
+        "sequential", single[0], single[1], single[2]
+    );
+
+    let mut reference = None;
+    for threads in thread_counts {
+        let pool = ParallelAnalytics::new(threads).expect("thread pool starts");
+        let timings = [
+            time(|| pool.connected_components(&csr)),
+            time(|| pool.pagerank(&csr, 0.85, 10)),
+            time(|| pool.triangle_count(&csr)),
+        ];
+        println!(
+            "{:<12} components {:>10.2?}   pagerank {:>10.2?}   triangles {:>10.2?}   speed-up {:.2}x / {:.2}x / {:.2}x",
+            format!("{threads} threads"),
+            timings[0],
+            timings[1],
+            timings[2],
+            single[0].as_secs_f64() / timings[0].as_secs_f64(),
+            single[1].as_secs_f64() / timings[1].as_secs_f64(),
+            single[2].as_secs_f64() / timings[2].as_secs_f64(),
+        );
+
+        let mut ranks = pool
+            .pagerank(&csr, 0.85, 10)
+            .into_iter()
+            .collect::<Vec<_>>();
+        ranks.sort_unstable_by_key(|&(id, _)| id);
+        let result = (
+            pool.connected_components(&csr),
+            ranks
+                .into_iter()
+                .map(|(id, rank)| (id, rank.to_bits()))
+                .collect::<Vec<_>>(),
+            pool.triangle_count(&csr),
+        );
+        match &reference {
+            None => reference = Some(result),
+            Some(reference) => assert!(
+                *reference == result,
+                "{threads} threads produced a different result"
+            ),
+        }
+    }
+
+    let (components, _, triangles) = reference.expect("at least one thread count ran");
+    assert_eq!(components, analytics::connected_components(&csr));
+    assert_eq!(triangles, analytics::triangle_count(&csr));
+}
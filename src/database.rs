@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use minerva::graph::Graph;
+use minerva::mql::diagnostic::Location;
+use minerva::mql::executor::{ExecutionError, Executor, Outcome};
+use minerva::mql::parser::{AST, ParseError, Parser};
+use minerva::mql::prepared::{Parameters, Prepared};
+use minerva::mql::script::Script;
+
+/// File inside a database directory holding its write statements.
+const LOG: &str = "log.mql";
+
+/// Graph that the shell and `minerva run` execute statements against.
+///
+/// A database opened on a directory appends every committed write statement to a log in that
+/// directory and replays the log when it is opened again. Without a directory it only lives in
+/// memory.
+pub struct Database {
+    executor: Executor,
+    log: Option<File>,
+    /// Statements of the open transaction, written out once it commits.
+    pending: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum StatementError {
+    Parse(ParseError),
+    Execution(ExecutionError),
+    /// The statement ran but could not be written to the log.
+    Log(io::Error),
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementError::Parse(error) => write!(f, "{error}"),
+            StatementError::Execution(error) => write!(f, "{error}"),
+            StatementError::Log(error) => write!(f, "cannot write to the database log: {error}"),
+        }
+    }
+}
+
+impl Error for StatementError {}
+
+#[derive(Debug)]
+pub enum OpenError {
+    Io(PathBuf, io::Error),
+    /// A logged statement no longer executes.
+    Replay(PathBuf, Location, Box<StatementError>),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::Io(path, error) => write!(f, "{}: {error}", path.display()),
+            OpenError::Replay(path, location, error) => {
+                write!(f, "{}:{location}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for OpenError {}
+
+impl Database {
+    pub fn in_memory() -> Self {
+        Self {
+            executor: Executor::new(Graph::new()),
+            log: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Opens the database stored in `directory`, creating it if it does not exist.
+    pub fn open(directory: &Path) -> Result<Self, OpenError> {
+        let path = directory.join(LOG);
+        let io_error = |error| OpenError::Io(path.clone(), error);
+
+        fs::create_dir_all(directory).map_err(io_error)?;
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(io_error(error)),
+        };
+
+        let mut database = Self::in_memory();
+        let script = Script::new(&source);
+        for (statement, span) in script.statements() {
+            database.execute(statement).map_err(|error| {
+                OpenError::Replay(path.clone(), script.location(span.start()), Box::new(error))
+            })?;
+        }
+        // committed transactions are logged whole, so an open one can only come from a torn write
+        database.executor = Executor::new(database.executor.into_graph());
+        database.pending.clear();
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(io_error)?;
+        database.log = Some(log);
+
+        Ok(database)
+    }
+
+    pub fn graph(&self) -> &Graph {
+        self.executor.graph()
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.executor.in_transaction()
+    }
+
+    /// Parses and executes a single statement.
+    pub fn execute(&mut self, statement: &str) -> Result<Outcome, StatementError> {
+        let ast = Parser::parse_one(statement).map_err(StatementError::Parse)?;
+        self.execute_parsed(ast, statement)
+    }
+
+    /// Executes `ast`, parsed from `statement`, which is what the log keeps if it writes.
+    pub fn execute_parsed(&mut self, ast: AST, statement: &str) -> Result<Outcome, StatementError> {
+        let prepared = Prepared::from(ast);
+        let is_write = !matches!(prepared.ast(), AST::Select(..));
+
+        // statements typed or logged as text have no values for parameters
+        let outcome = self
+            .executor
+            .execute_prepared(&prepared, &Parameters::new())
+            .map_err(StatementError::Execution)?;
+
+        match outcome {
+            Outcome::RolledBack => self.pending.clear(),
+            _ if is_write => {
+                let mut statement = statement.trim().to_string();
+                if !statement.ends_with(';') {
+                    statement.push(';');
+                }
+                self.pending.push(statement);
+            }
+            _ => {}
+        }
+
+        if !self.executor.in_transaction() {
+            self.flush().map_err(StatementError::Log)?;
+        }
+
+        Ok(outcome)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let pending = std::mem::take(&mut self.pending);
+        let Some(log) = &mut self.log else {
+            return Ok(());
+        };
+
+        let mut entry = String::new();
+        for statement in pending {
+            entry.push_str(&statement);
+            entry.push('\n');
+        }
+        log.write_all(entry.as_bytes())
+    }
+}
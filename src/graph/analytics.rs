@@ -0,0 +1,365 @@
+//! Whole-graph algorithms over any [`Topology`].
+//!
+//! Every algorithm runs against the live [`Graph`](crate::graph::Graph) as well as against a
+//! [`CsrSnapshot`](crate::graph::CsrSnapshot). On a snapshot they switch to implementations
+//! working on dense indices and flat vectors, which is considerably faster for anything that
+//! touches every edge.
+
+pub mod parallel;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::topology::Topology;
+
+/// Weakly connected components, mapping every node id to the smallest node id in its component.
+pub fn connected_components(graph: &impl Topology) -> HashMap<usize, usize> {
+    if let Some(csr) = graph.as_csr() {
+        let roots = dense::connected_components(csr);
+        return roots
+            .iter()
+            .enumerate()
+            .map(|(index, &root)| (csr.id_of(index), csr.id_of(root as usize)))
+            .collect();
+    }
+
+    let mut parent = graph
+        .node_ids()
+        .map(|id| (id, id))
+        .collect::<HashMap<_, _>>();
+
+    fn find(parent: &mut HashMap<usize, usize>, id: usize) -> usize {
+        let mut root = id;
+        while parent[&root] != root {
+            root = parent[&root];
+        }
+
+        let mut current = id;
+        while parent[&current] != root {
+            let next = parent[&current];
+            parent.insert(current, root);
+            current = next;
+        }
+
+        root
+    }
+
+    for id in graph.node_ids() {
+        for neighbor in graph.neighbors(id) {
+            let (a, b) = (find(&mut parent, id), find(&mut parent, neighbor));
+            if a != b {
+                parent.insert(a.max(b), a.min(b));
+            }
+        }
+    }
+
+    graph
+        .node_ids()
+        .map(|id| (id, find(&mut parent, id)))
+        .collect()
+}
+
+/// PageRank with the given damping factor, redistributing the rank of dangling nodes evenly.
+pub fn pagerank(graph: &impl Topology, damping: f64, iterations: usize) -> HashMap<usize, f64> {
+    if let Some(csr) = graph.as_csr() {
+        let ranks = dense::pagerank(csr, damping, iterations);
+        return ranks
+            .into_iter()
+            .enumerate()
+            .map(|(index, rank)| (csr.id_of(index), rank))
+            .collect();
+    }
+
+    let count = graph.node_count();
+    if count == 0 {
+        return HashMap::new();
+    }
+
+    let initial = 1.0 / count as f64;
+    let mut ranks = graph
+        .node_ids()
+        .map(|id| (id, initial))
+        .collect::<HashMap<_, _>>();
+
+    for _ in 0..iterations {
+        let mut next = graph
+            .node_ids()
+            .map(|id| (id, 0.0))
+            .collect::<HashMap<_, _>>();
+        let mut dangling = 0.0;
+
+        for id in graph.node_ids() {
+            let rank = ranks[&id];
+            let degree = graph.out_degree(id);
+            if degree == 0 {
+                dangling += rank;
+                continue;
+            }
+
+            let share = rank / degree as f64;
+            for neighbor in graph.neighbors(id) {
+                *next
+                    .get_mut(&neighbor)
+                    .expect("edges only point at existing nodes") += share;
+            }
+        }
+
+        let base = (1.0 - damping + damping * dangling) / count as f64;
+        for rank in next.values_mut() {
+            *rank = base + damping * *rank;
+        }
+
+        ranks = next;
+    }
+
+    ranks
+}
+
+/// Number of triangles in the graph, ignoring edge direction, self loops and parallel edges.
+pub fn triangle_count(graph: &impl Topology) -> usize {
+    if let Some(csr) = graph.as_csr() {
+        return dense::triangle_count(csr);
+    }
+
+    let mut adjacency: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for id in graph.node_ids() {
+        for neighbor in graph.neighbors(id).filter(|&n| n != id) {
+            adjacency.entry(id).or_default().insert(neighbor);
+            adjacency.entry(neighbor).or_default().insert(id);
+        }
+    }
+
+    let mut triangles = 0;
+    for (&u, neighbors) in &adjacency {
+        for &v in neighbors.iter().filter(|&&v| v > u) {
+            triangles += adjacency[&v]
+                .iter()
+                .filter(|&&w| w > v && neighbors.contains(&w))
+                .count();
+        }
+    }
+
+    triangles
+}
+
+mod dense {
+    use std::cmp::Ordering;
+
+    use crate::graph::csr::CsrSnapshot;
+
+    /// Component root per dense index; the root is always the smallest index in the component.
+    pub fn connected_components(csr: &CsrSnapshot) -> Vec<u32> {
+        let mut parent = (0..csr.node_count() as u32).collect::<Vec<_>>();
+
+        fn find(parent: &mut [u32], index: u32) -> u32 {
+            let mut root = index;
+            while parent[root as usize] != root {
+                root = parent[root as usize];
+            }
+
+            let mut current = index;
+            while parent[current as usize] != root {
+                let next = parent[current as usize];
+                parent[current as usize] = root;
+                current = next;
+            }
+
+            root
+        }
+
+        for index in 0..csr.node_count() {
+            for &neighbor in csr.neighbor_indices(index) {
+                let (a, b) = (find(&mut parent, index as u32), find(&mut parent, neighbor));
+                if a != b {
+                    parent[a.max(b) as usize] = a.min(b);
+                }
+            }
+        }
+
+        (0..csr.node_count() as u32)
+            .map(|index| find(&mut parent, index))
+            .collect()
+    }
+
+    pub fn pagerank(csr: &CsrSnapshot, damping: f64, iterations: usize) -> Vec<f64> {
+        let count = csr.node_count();
+        let mut ranks = vec![1.0 / count as f64; count];
+        let mut next = vec![0.0; count];
+
+        for _ in 0..iterations {
+            next.fill(0.0);
+            let mut dangling = 0.0;
+
+            for (index, &rank) in ranks.iter().enumerate() {
+                let targets = csr.neighbor_indices(index);
+                if targets.is_empty() {
+                    dangling += rank;
+                    continue;
+                }
+
+                let share = rank / targets.len() as f64;
+                for &target in targets {
+                    next[target as usize] += share;
+                }
+            }
+
+            let base = (1.0 - damping + damping * dangling) / count as f64;
+            for rank in next.iter_mut() {
+                *rank = base + damping * *rank;
+            }
+
+            std::mem::swap(&mut ranks, &mut next);
+        }
+
+        ranks
+    }
+
+    /// Sorted, deduplicated neighbours per index with edge direction and self loops removed.
+    pub fn undirected_adjacency(csr: &CsrSnapshot) -> Vec<Vec<u32>> {
+        let mut adjacency = vec![Vec::new(); csr.node_count()];
+        for index in 0..csr.node_count() {
+            for &neighbor in csr.neighbor_indices(index) {
+                if neighbor as usize != index {
+                    adjacency[index].push(neighbor);
+                    adjacency[neighbor as usize].push(index as u32);
+                }
+            }
+        }
+
+        for neighbors in adjacency.iter_mut() {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+
+        adjacency
+    }
+
+    /// Triangles whose smallest index is `u`.
+    pub fn triangles_at(adjacency: &[Vec<u32>], u: usize) -> usize {
+        let upper = |index: usize, above: u32| {
+            let neighbors = &adjacency[index];
+            &neighbors[neighbors.partition_point(|&n| n <= above)..]
+        };
+
+        let mut triangles = 0;
+        for &v in upper(u, u as u32) {
+            let (mut a, mut b) = (
+                upper(u, v).iter().peekable(),
+                upper(v as usize, v).iter().peekable(),
+            );
+            while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+                match x.cmp(y) {
+                    Ordering::Less => {
+                        a.next();
+                    }
+                    Ordering::Greater => {
+                        b.next();
+                    }
+                    Ordering::Equal => {
+                        triangles += 1;
+                        a.next();
+                        b.next();
+                    }
+                }
+            }
+        }
+
+        triangles
+    }
+
+    pub fn triangle_count(csr: &CsrSnapshot) -> usize {
+        let adjacency = undirected_adjacency(csr);
+        (0..adjacency.len())
+            .map(|u| triangles_at(&adjacency, u))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    /// Two components, one of them with two triangles, a self loop, a parallel edge and a
+    /// dangling node.
+    fn sample() -> Graph {
+        let mut graph = Graph::new();
+        for _ in 0..8 {
+            graph.add_node(Vec::new(), HashMap::new()).unwrap();
+        }
+        let edges = [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 0),
+            (2, 3),
+            (3, 1),
+            (3, 3),
+            (3, 4),
+            (5, 6),
+            (6, 5),
+            (6, 7),
+        ];
+        for (from, to) in edges {
+            graph.add_edge(from, to, "link".to_string()).unwrap();
+        }
+
+        graph
+    }
+
+    #[test]
+    fn traversals_agree_on_graphs_and_snapshots() {
+        let graph = sample();
+        let csr = graph.snapshot();
+
+        for from in 0..8 {
+            assert_eq!(graph.bfs(from), csr.bfs(from));
+            for to in 0..8 {
+                assert_eq!(graph.dfs(from, to), csr.dfs(from, to));
+            }
+        }
+        assert_eq!(csr.dfs(0, 4), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(csr.dfs(0, 5), None);
+    }
+
+    #[test]
+    fn components_and_triangles_agree_on_graphs_and_snapshots() {
+        let graph = sample();
+        let csr = graph.snapshot();
+
+        let components = connected_components(&graph);
+        assert_eq!(components, connected_components(&csr));
+        assert_eq!(
+            components,
+            HashMap::from([
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (4, 0),
+                (5, 5),
+                (6, 5),
+                (7, 5),
+            ])
+        );
+
+        assert_eq!(triangle_count(&graph), 2);
+        assert_eq!(triangle_count(&csr), 2);
+    }
+
+    #[test]
+    fn pagerank_agrees_on_graphs_and_snapshots() {
+        let graph = sample();
+        let (sparse, dense) = (
+            pagerank(&graph, 0.85, 30),
+            pagerank(&graph.snapshot(), 0.85, 30),
+        );
+
+        assert_eq!(sparse.len(), 8);
+        assert_eq!(dense.len(), 8);
+        for (id, rank) in &sparse {
+            // summation order differs between the two, so only agree up to rounding
+            assert!((rank - dense[id]).abs() < 1e-12, "rank of {id}");
+        }
+        assert!((dense.values().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
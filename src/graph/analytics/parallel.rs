@@ -0,0 +1,320 @@
+//! Multi-threaded versions of the whole-graph algorithms.
+//!
+//! They run on a [`CsrSnapshot`] inside a work-stealing thread pool of configurable size.
+//! Results do not depend on the number of threads or on scheduling: components are the unique
+//! fixed point of min-label propagation, PageRank pulls contributions in a fixed order and sums
+//! floating point values over fixed-size chunks, and triangle counts are plain integer sums.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::graph::analytics::dense;
+use crate::graph::csr::CsrSnapshot;
+
+/// Number of values summed sequentially before partial sums are combined.
+const CHUNK: usize = 4096;
+
+/// Runs analytics on a dedicated thread pool.
+pub struct ParallelAnalytics {
+    pool: ThreadPool,
+}
+
+impl ParallelAnalytics {
+    /// Creates a pool with `threads` workers, or one per core if `threads` is zero.
+    pub fn new(threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("minerva-analytics-{index}"))
+            .build()?;
+
+        Ok(Self { pool })
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Weakly connected components, mapping every node id to the smallest node id in its
+    /// component.
+    pub fn connected_components(&self, csr: &CsrSnapshot) -> HashMap<usize, usize> {
+        let roots = self.pool.install(|| connected_components(csr));
+
+        roots
+            .iter()
+            .enumerate()
+            .map(|(index, &root)| (csr.id_of(index), csr.id_of(root as usize)))
+            .collect()
+    }
+
+    /// PageRank with the given damping factor, redistributing the rank of dangling nodes evenly.
+    pub fn pagerank(
+        &self,
+        csr: &CsrSnapshot,
+        damping: f64,
+        iterations: usize,
+    ) -> HashMap<usize, f64> {
+        let ranks = self.pool.install(|| pagerank(csr, damping, iterations));
+
+        ranks
+            .into_iter()
+            .enumerate()
+            .map(|(index, rank)| (csr.id_of(index), rank))
+            .collect()
+    }
+
+    /// Number of triangles in the graph, ignoring edge direction, self loops and parallel edges.
+    pub fn triangle_count(&self, csr: &CsrSnapshot) -> usize {
+        self.pool.install(|| {
+            let adjacency = dense::undirected_adjacency(csr);
+            (0..adjacency.len())
+                .into_par_iter()
+                .map(|u| dense::triangles_at(&adjacency, u))
+                .sum()
+        })
+    }
+}
+
+/// Min-label propagation with pointer jumping.
+///
+/// Labels only ever decrease to the index of another node in the same component, so the
+/// propagation converges to the smallest index of every component however updates interleave.
+fn connected_components(csr: &CsrSnapshot) -> Vec<u32> {
+    let labels = (0..csr.node_count() as u32)
+        .map(AtomicU32::new)
+        .collect::<Vec<_>>();
+
+    loop {
+        let changed = AtomicBool::new(false);
+
+        (0..csr.node_count()).into_par_iter().for_each(|index| {
+            for &neighbor in csr.neighbor_indices(index) {
+                let (a, b) = (
+                    labels[index].load(Ordering::Relaxed),
+                    labels[neighbor as usize].load(Ordering::Relaxed),
+                );
+                if a < b {
+                    labels[neighbor as usize].fetch_min(a, Ordering::Relaxed);
+                    changed.store(true, Ordering::Relaxed);
+                } else if b < a {
+                    labels[index].fetch_min(b, Ordering::Relaxed);
+                    changed.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+
+        labels.par_iter().for_each(|label| {
+            let mut current = label.load(Ordering::Relaxed);
+            loop {
+                let next = labels[current as usize].load(Ordering::Relaxed);
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+            label.fetch_min(current, Ordering::Relaxed);
+        });
+
+        if !changed.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+
+    labels.into_iter().map(AtomicU32::into_inner).collect()
+}
+
+/// Pull-based PageRank; every node sums the contributions of its sources in index order.
+fn pagerank(csr: &CsrSnapshot, damping: f64, iterations: usize) -> Vec<f64> {
+    let count = csr.node_count();
+    let (offsets, sources) = reverse(csr);
+    let degrees = (0..count)
+        .map(|index| csr.neighbor_indices(index).len())
+        .collect::<Vec<_>>();
+
+    let mut ranks = vec![1.0 / count as f64; count];
+    let mut next = vec![0.0; count];
+
+    for _ in 0..iterations {
+        let dangling = ranks
+            .par_chunks(CHUNK)
+            .zip(degrees.par_chunks(CHUNK))
+            .map(|(ranks, degrees)| {
+                ranks
+                    .iter()
+                    .zip(degrees)
+                    .filter(|&(_, &degree)| degree == 0)
+                    .map(|(rank, _)| rank)
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .sum::<f64>();
+
+        let base = (1.0 - damping + damping * dangling) / count as f64;
+        next.par_iter_mut().enumerate().for_each(|(index, rank)| {
+            let incoming = sources[offsets[index]..offsets[index + 1]]
+                .iter()
+                .map(|&source| ranks[source as usize] / degrees[source as usize] as f64)
+                .sum::<f64>();
+            *rank = base + damping * incoming;
+        });
+
+        std::mem::swap(&mut ranks, &mut next);
+    }
+
+    ranks
+}
+
+/// Incoming edges in CSR form: the sources of the edges into index `i`, in ascending order,
+/// are `sources[offsets[i]..offsets[i + 1]]`.
+fn reverse(csr: &CsrSnapshot) -> (Vec<usize>, Vec<u32>) {
+    let count = csr.node_count();
+    let mut offsets = vec![0; count + 1];
+    for index in 0..count {
+        for &target in csr.neighbor_indices(index) {
+            offsets[target as usize + 1] += 1;
+        }
+    }
+    for index in 0..count {
+        offsets[index + 1] += offsets[index];
+    }
+
+    let mut fill = offsets.clone();
+    let mut sources = vec![0; csr.edge_count()];
+    for index in 0..count {
+        for &target in csr.neighbor_indices(index) {
+            sources[fill[target as usize]] = index as u32;
+            fill[target as usize] += 1;
+        }
+    }
+
+    (offsets, sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::graph::Graph;
+    use crate::graph::analytics;
+
+    /// More nodes than fit in one chunk, with sparse random edges so that there are many
+    /// components, dangling nodes and a few triangles.
+    const NODES: usize = 3 * CHUNK + 17;
+
+    fn sample() -> Graph {
+        let mut graph = Graph::new();
+        for _ in 0..NODES {
+            graph.add_node(Vec::new(), HashMap::new()).unwrap();
+        }
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        for from in 0..NODES {
+            for _ in 0..next() % 3 {
+                // mostly short edges, so that triangles are not left to chance
+                let to = (from + next() % 8) % NODES;
+                graph.add_edge(from, to, "link".to_string()).unwrap();
+            }
+        }
+
+        graph
+    }
+
+    fn pools() -> Vec<ParallelAnalytics> {
+        [1, 2, 8]
+            .into_iter()
+            .map(|threads| ParallelAnalytics::new(threads).unwrap())
+            .collect()
+    }
+
+    fn assert_close(a: &HashMap<usize, f64>, b: &HashMap<usize, f64>) {
+        assert_eq!(a.len(), b.len());
+        for (id, rank) in a {
+            assert!(
+                (rank - b[id]).abs() < 1e-12,
+                "rank of {id}: {rank} vs {}",
+                b[id]
+            );
+        }
+    }
+
+    #[test]
+    fn pools_have_the_requested_number_of_threads() {
+        assert_eq!(ParallelAnalytics::new(1).unwrap().threads(), 1);
+        assert_eq!(ParallelAnalytics::new(3).unwrap().threads(), 3);
+        assert!(ParallelAnalytics::new(0).unwrap().threads() >= 1);
+    }
+
+    #[test]
+    fn components_match_the_sequential_result_for_every_thread_count() {
+        let graph = sample();
+        let csr = graph.snapshot();
+        let expected = analytics::connected_components(&graph);
+
+        assert!(expected.values().collect::<HashSet<_>>().len() > 1);
+        for pool in pools() {
+            assert_eq!(
+                pool.connected_components(&csr),
+                expected,
+                "{} threads",
+                pool.threads()
+            );
+        }
+    }
+
+    #[test]
+    fn triangles_match_the_sequential_result_for_every_thread_count() {
+        let graph = sample();
+        let csr = graph.snapshot();
+        let expected = analytics::triangle_count(&graph);
+
+        assert!(expected > 0);
+        assert_eq!(analytics::triangle_count(&csr), expected);
+        for pool in pools() {
+            assert_eq!(
+                pool.triangle_count(&csr),
+                expected,
+                "{} threads",
+                pool.threads()
+            );
+        }
+    }
+
+    #[test]
+    fn pagerank_is_identical_for_every_thread_count() {
+        let csr = sample().snapshot();
+        let pools = pools();
+        let expected = pools[0].pagerank(&csr, 0.85, 20);
+
+        for pool in &pools[1..] {
+            // the summation order is fixed, so the results are equal to the last bit
+            assert_eq!(
+                pool.pagerank(&csr, 0.85, 20),
+                expected,
+                "{} threads",
+                pool.threads()
+            );
+        }
+    }
+
+    #[test]
+    fn pagerank_matches_the_sequential_result_up_to_rounding() {
+        let graph = sample();
+        let csr = graph.snapshot();
+        let parallel = ParallelAnalytics::new(0).unwrap().pagerank(&csr, 0.85, 20);
+
+        // the sequential versions sum in other orders, on a graph even in hash map order
+        assert_close(&parallel, &analytics::pagerank(&csr, 0.85, 20));
+        assert_close(&parallel, &analytics::pagerank(&graph, 0.85, 20));
+        assert!((parallel.values().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
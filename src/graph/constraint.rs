@@ -0,0 +1,512 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::graph::error::GraphError;
+use crate::graph::graph::Node;
+use crate::graph::symbols::Symbol;
+use crate::graph::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// No two nodes with `label` share a value for `property`.
+    Unique { label: String, property: String },
+    /// Every node with `label` has `property`.
+    Exists { label: String, property: String },
+}
+
+impl Constraint {
+    pub fn label(&self) -> &str {
+        match self {
+            Constraint::Unique { label, .. } | Constraint::Exists { label, .. } => label,
+        }
+    }
+
+    pub fn property(&self) -> &str {
+        match self {
+            Constraint::Unique { property, .. } | Constraint::Exists { property, .. } => property,
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Constraint::Unique { .. } => "unique",
+            Constraint::Exists { .. } => "exists",
+        };
+
+        write!(f, "{kind} on {}({})", self.label(), self.property())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstraintViolation {
+    /// `existing` already holds `value` for the constrained property.
+    Duplicate {
+        constraint: Constraint,
+        value: Value,
+        existing: usize,
+    },
+    Missing {
+        constraint: Constraint,
+    },
+}
+
+impl fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintViolation::Duplicate {
+                constraint,
+                value,
+                existing,
+            } => write!(
+                f,
+                "constraint `{constraint}` violated, node {existing} already has {} = {value}",
+                constraint.property()
+            ),
+            ConstraintViolation::Missing { constraint } => write!(
+                f,
+                "constraint `{constraint}` violated, property `{}` is missing",
+                constraint.property()
+            ),
+        }
+    }
+}
+
+/// Hashable form of a [`Value`] for unique indexes and for telling rows apart.
+///
+/// Numbers are keyed the way `=` compares them: a float holding a whole number that fits an int
+/// has the key of that int, so `1` and `1.0` are the same key. Every NaN has one key as well.
+///
+/// Keys are ordered by type first, so all strings sharing a prefix are next to each other. Other
+/// floats are ordered by their bits, which only keeps equal floats together.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum IndexKey {
+    String(String),
+    Int(i64),
+    Float(u64),
+    Bool(bool),
+    List(Vec<IndexKey>),
+    Map(Vec<(String, IndexKey)>),
+}
+
+impl IndexKey {
+    fn float(float: f64) -> Self {
+        // `i64::MIN as f64` is exact, `i64::MAX as f64` rounds up to just past the largest int
+        let is_int = float.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&float);
+        match float {
+            _ if is_int => IndexKey::Int(float as i64),
+            _ if float.is_nan() => IndexKey::Float(f64::NAN.to_bits()),
+            _ => IndexKey::Float(float.to_bits()),
+        }
+    }
+}
+
+impl From<&Value> for IndexKey {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::String(string) => IndexKey::String(string.clone()),
+            Value::Int(int) => IndexKey::Int(*int),
+            Value::Float(float) => IndexKey::float(*float),
+            Value::Bool(bool) => IndexKey::Bool(*bool),
+            Value::List(values) => IndexKey::List(values.iter().map(IndexKey::from).collect()),
+            Value::Map(entries) => IndexKey::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), IndexKey::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    constraint: Constraint,
+    label: Symbol,
+    property: Symbol,
+    /// Owner of every value of the property, only maintained for unique constraints.
+    index: BTreeMap<IndexKey, usize>,
+}
+
+/// Active constraints of a graph together with the indexes backing unique constraints.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Constraints {
+    entries: Vec<Entry>,
+}
+
+impl Constraints {
+    pub fn iter(&self) -> impl Iterator<Item = &Constraint> {
+        self.entries.iter().map(|entry| &entry.constraint)
+    }
+
+    /// Adds a constraint after validating it against `nodes`, which must hold every node of
+    /// the graph in a stable order. Adding a constraint that already exists does nothing.
+    pub fn add<'n>(
+        &mut self,
+        constraint: Constraint,
+        label: Symbol,
+        property: Symbol,
+        nodes: impl Iterator<Item = &'n Node>,
+    ) -> Result<(), GraphError> {
+        if self.iter().any(|existing| *existing == constraint) {
+            return Ok(());
+        }
+
+        let mut entry = Entry {
+            constraint,
+            label,
+            property,
+            index: BTreeMap::new(),
+        };
+
+        for node in nodes.filter(|node| node.has_label(label)) {
+            entry
+                .check(Some(node.id()), node.properties())
+                .map_err(|violation| GraphError::Constraint(Some(node.id()), violation))?;
+            entry.insert(node.id(), node.properties());
+        }
+
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Checks a node about to be written with the given labels and properties.
+    pub fn check(
+        &self,
+        id: Option<usize>,
+        labels: &[Symbol],
+        properties: &HashMap<Symbol, Value>,
+    ) -> Result<(), ConstraintViolation> {
+        self.applicable(labels)
+            .try_for_each(|entry| entry.check(id, properties))
+    }
+
+    pub fn insert(&mut self, id: usize, labels: &[Symbol], properties: &HashMap<Symbol, Value>) {
+        for entry in self.applicable_mut(labels) {
+            entry.insert(id, properties);
+        }
+    }
+
+    pub fn remove(&mut self, id: usize, labels: &[Symbol], properties: &HashMap<Symbol, Value>) {
+        for entry in self.applicable_mut(labels) {
+            if let Some(value) = properties.get(&entry.property) {
+                let key = IndexKey::from(value);
+                if entry.index.get(&key) == Some(&id) {
+                    entry.index.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Nodes with `label` whose string value of `property` starts with `prefix`, together with
+    /// those holding a list in it, or `None` if no unique constraint indexes the property.
+    pub fn prefixed(
+        &self,
+        label: Symbol,
+        property: Symbol,
+        prefix: &str,
+    ) -> Option<impl Iterator<Item = usize>> {
+        let entry = self.entries.iter().find(|entry| {
+            matches!(entry.constraint, Constraint::Unique { .. })
+                && entry.label == label
+                && entry.property == property
+        })?;
+
+        let strings = entry
+            .index
+            .range(IndexKey::String(prefix.to_string())..)
+            .take_while(move |(key, _)| {
+                matches!(key, IndexKey::String(string) if string.starts_with(prefix))
+            });
+        // a list matches when one of its elements does, which the index cannot tell
+        let lists = entry
+            .index
+            .range(IndexKey::List(Vec::new())..IndexKey::Map(Vec::new()));
+
+        Some(strings.chain(lists).map(|(_, &id)| id))
+    }
+
+    fn applicable(&self, labels: &[Symbol]) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| labels.contains(&entry.label))
+    }
+
+    fn applicable_mut(&mut self, labels: &[Symbol]) -> impl Iterator<Item = &mut Entry> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| labels.contains(&entry.label))
+    }
+}
+
+impl Entry {
+    fn check(
+        &self,
+        id: Option<usize>,
+        properties: &HashMap<Symbol, Value>,
+    ) -> Result<(), ConstraintViolation> {
+        let value = properties.get(&self.property);
+
+        match (&self.constraint, value) {
+            (Constraint::Exists { .. }, None) => Err(ConstraintViolation::Missing {
+                constraint: self.constraint.clone(),
+            }),
+            (Constraint::Unique { .. }, Some(value)) => {
+                match self.index.get(&IndexKey::from(value)) {
+                    Some(&existing) if Some(existing) != id => {
+                        Err(ConstraintViolation::Duplicate {
+                            constraint: self.constraint.clone(),
+                            value: value.clone(),
+                            existing,
+                        })
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn insert(&mut self, id: usize, properties: &HashMap<Symbol, Value>) {
+        if let (Constraint::Unique { .. }, Some(value)) =
+            (&self.constraint, properties.get(&self.property))
+        {
+            self.index.insert(IndexKey::from(value), id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    fn unique(label: &str, property: &str) -> Constraint {
+        Constraint::Unique {
+            label: label.to_string(),
+            property: property.to_string(),
+        }
+    }
+
+    fn exists(label: &str, property: &str) -> Constraint {
+        Constraint::Exists {
+            label: label.to_string(),
+            property: property.to_string(),
+        }
+    }
+
+    fn author(graph: &mut Graph, name: &str) -> Result<usize, GraphError> {
+        let properties = HashMap::from([("name".to_string(), Value::String(name.to_string()))]);
+        graph.add_node(vec!["author".to_string()], properties)
+    }
+
+    fn duplicate(name: &str, existing: usize) -> ConstraintViolation {
+        ConstraintViolation::Duplicate {
+            constraint: unique("author", "name"),
+            value: Value::String(name.to_string()),
+            existing,
+        }
+    }
+
+    #[test]
+    fn unique_values_are_enforced_on_create_and_update() {
+        let mut graph = Graph::new();
+        graph.create_constraint(unique("author", "name")).unwrap();
+        let tolkien = author(&mut graph, "Tolkien").unwrap();
+        let herbert = author(&mut graph, "Herbert").unwrap();
+
+        assert_eq!(
+            author(&mut graph, "Tolkien"),
+            Err(GraphError::Constraint(None, duplicate("Tolkien", tolkien)))
+        );
+        assert_eq!(
+            graph.set_property(herbert, "name", Value::String("Tolkien".to_string())),
+            Err(GraphError::Constraint(
+                Some(herbert),
+                duplicate("Tolkien", tolkien)
+            ))
+        );
+        // rewriting a node's own value is not a conflict, nor are other labels
+        assert!(
+            graph
+                .set_property(tolkien, "name", Value::String("Tolkien".to_string()))
+                .is_ok()
+        );
+        let properties =
+            HashMap::from([("name".to_string(), Value::String("Tolkien".to_string()))]);
+        assert!(graph.add_node(vec!["book".to_string()], properties).is_ok());
+        assert_eq!(graph.node_count(), 3);
+    }
+
+    #[test]
+    fn existence_is_enforced_on_create_and_update() {
+        let mut graph = Graph::new();
+        graph.create_constraint(exists("author", "name")).unwrap();
+        let missing = ConstraintViolation::Missing {
+            constraint: exists("author", "name"),
+        };
+
+        assert_eq!(
+            graph.add_node(vec!["author".to_string()], HashMap::new()),
+            Err(GraphError::Constraint(None, missing.clone()))
+        );
+        let tolkien = author(&mut graph, "Tolkien").unwrap();
+        assert_eq!(
+            graph.remove_property(tolkien, "name"),
+            Err(GraphError::Constraint(Some(tolkien), missing))
+        );
+        assert_eq!(
+            graph.property(tolkien, "name"),
+            Some(&Value::String("Tolkien".to_string()))
+        );
+    }
+
+    #[test]
+    fn constraints_violated_by_existing_data_are_refused() {
+        let mut graph = Graph::new();
+        let first = author(&mut graph, "Tolkien").unwrap();
+        let second = author(&mut graph, "Tolkien").unwrap();
+        let anonymous = graph
+            .add_node(vec!["author".to_string()], HashMap::new())
+            .unwrap();
+
+        // the error names the node that clashes and the one it clashes with
+        assert_eq!(
+            graph.create_constraint(unique("author", "name")),
+            Err(GraphError::Constraint(
+                Some(second),
+                duplicate("Tolkien", first)
+            ))
+        );
+        assert_eq!(
+            graph.create_constraint(exists("author", "name")),
+            Err(GraphError::Constraint(
+                Some(anonymous),
+                ConstraintViolation::Missing {
+                    constraint: exists("author", "name"),
+                }
+            ))
+        );
+        assert_eq!(graph.constraints().count(), 0);
+        assert!(author(&mut graph, "Tolkien").is_ok());
+    }
+
+    #[test]
+    fn index_follows_changed_and_removed_values() {
+        let mut graph = Graph::new();
+        graph.create_constraint(unique("author", "name")).unwrap();
+        let tolkien = author(&mut graph, "Tolkien").unwrap();
+        let herbert = author(&mut graph, "Herbert").unwrap();
+
+        graph
+            .set_property(tolkien, "name", Value::String("J.R.R. Tolkien".to_string()))
+            .unwrap();
+        let other = author(&mut graph, "Tolkien").unwrap();
+        assert_eq!(
+            author(&mut graph, "J.R.R. Tolkien"),
+            Err(GraphError::Constraint(
+                None,
+                duplicate("J.R.R. Tolkien", tolkien)
+            ))
+        );
+
+        graph.remove_property(herbert, "name").unwrap();
+        assert!(author(&mut graph, "Herbert").is_ok());
+        // the removed node no longer owns its old value, so setting it back clashes
+        assert_eq!(
+            graph.set_property(herbert, "name", Value::String("Tolkien".to_string())),
+            Err(GraphError::Constraint(
+                Some(herbert),
+                duplicate("Tolkien", other)
+            ))
+        );
+    }
+
+    #[test]
+    fn prefixed_scans_strings_with_the_prefix_and_every_list() {
+        let mut graph = Graph::new();
+        graph.create_constraint(unique("author", "name")).unwrap();
+        let tolkien = author(&mut graph, "Tolkien").unwrap();
+        let toole = author(&mut graph, "Toole").unwrap();
+        author(&mut graph, "Herbert").unwrap();
+        author(&mut graph, "To").unwrap();
+        let list = graph
+            .add_node(
+                vec!["author".to_string()],
+                HashMap::from([(
+                    "name".to_string(),
+                    Value::List(vec![Value::String("Herbert".to_string())]),
+                )]),
+            )
+            .unwrap();
+        graph
+            .add_node(
+                vec!["author".to_string()],
+                HashMap::from([("name".to_string(), Value::Int(7))]),
+            )
+            .unwrap();
+
+        let mut ids = graph
+            .nodes_with_prefix("author", "name", "Too")
+            .expect("the property is indexed")
+            .chain(graph.nodes_with_prefix("author", "name", "Tol").unwrap())
+            .map(|node| node.id())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, [tolkien, toole, list, list]);
+
+        assert!(graph.nodes_with_prefix("author", "born", "T").is_none());
+        assert!(graph.nodes_with_prefix("book", "name", "T").is_none());
+    }
+
+    #[test]
+    fn numbers_equal_under_comparison_share_a_key() {
+        let key = |value: Value| IndexKey::from(&value);
+
+        assert_eq!(key(Value::Float(1.0)), key(Value::Int(1)));
+        assert_eq!(key(Value::Float(-0.0)), key(Value::Int(0)));
+        assert_eq!(
+            key(Value::Float(-2.0f64.powi(63))),
+            key(Value::Int(i64::MIN))
+        );
+        assert_eq!(key(Value::Float(f64::NAN)), key(Value::Float(-f64::NAN)));
+        assert_eq!(
+            key(Value::List(vec![Value::Float(3.0)])),
+            key(Value::List(vec![Value::Int(3)]))
+        );
+        assert_ne!(key(Value::Float(1.5)), key(Value::Int(1)));
+        assert_ne!(
+            key(Value::Float(2.0f64.powi(63))),
+            key(Value::Int(i64::MAX))
+        );
+        assert_ne!(key(Value::Float(f64::INFINITY)), key(Value::Int(i64::MAX)));
+    }
+
+    #[test]
+    fn unique_numbers_are_compared_by_value() {
+        let mut graph = Graph::new();
+        graph.create_constraint(unique("book", "pages")).unwrap();
+        let book = |graph: &mut Graph, pages: Value| {
+            graph.add_node(
+                vec!["book".to_string()],
+                HashMap::from([("pages".to_string(), pages)]),
+            )
+        };
+
+        let first = book(&mut graph, Value::Int(100)).unwrap();
+        assert_eq!(
+            book(&mut graph, Value::Float(100.0)),
+            Err(GraphError::Constraint(
+                None,
+                ConstraintViolation::Duplicate {
+                    constraint: unique("book", "pages"),
+                    value: Value::Float(100.0),
+                    existing: first,
+                }
+            ))
+        );
+        assert!(book(&mut graph, Value::Float(100.5)).is_ok());
+    }
+}
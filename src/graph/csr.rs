@@ -0,0 +1,249 @@
+use crate::graph::graph::Graph;
+use crate::graph::symbols::{Symbol, SymbolTable};
+use crate::graph::topology::Topology;
+
+const ABSENT: u32 = u32::MAX;
+
+/// Read-only compressed-sparse-row copy of a [`Graph`].
+///
+/// Node ids are remapped to dense indices in ascending id order. The outgoing edges of the node
+/// at index `i` are `targets[offsets[i]..offsets[i + 1]]`. Labels are kept as the [`Symbol`]s of
+/// the graph they were taken from, alongside a copy of its [`SymbolTable`].
+pub struct CsrSnapshot {
+    ids: Vec<usize>,
+    indices: Vec<u32>,
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+    edge_labels: Vec<Symbol>,
+    label_offsets: Vec<usize>,
+    node_labels: Vec<Symbol>,
+    symbols: SymbolTable,
+}
+
+impl CsrSnapshot {
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Dense index of the node with the given id.
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        match self.indices.get(id) {
+            Some(&index) if index != ABSENT => Some(index as usize),
+            _ => None,
+        }
+    }
+
+    /// Node id stored at the given dense index.
+    pub fn id_of(&self, index: usize) -> usize {
+        self.ids[index]
+    }
+
+    /// Dense indices of the targets of every outgoing edge of the node at `index`.
+    pub fn neighbor_indices(&self, index: usize) -> &[u32] {
+        &self.targets[self.offsets[index]..self.offsets[index + 1]]
+    }
+
+    pub fn get_neighbors(&self, id: usize) -> Option<Vec<usize>> {
+        let index = self.index_of(id)?;
+        Some(
+            self.neighbor_indices(index)
+                .iter()
+                .map(|&target| self.ids[target as usize])
+                .collect(),
+        )
+    }
+
+    /// Outgoing edges of the node with the given id as `(target id, edge label)` pairs.
+    pub fn outgoing(&self, id: usize) -> impl Iterator<Item = (usize, &str)> + '_ {
+        let range = match self.index_of(id) {
+            Some(index) => self.offsets[index]..self.offsets[index + 1],
+            None => 0..0,
+        };
+
+        range.map(|edge| {
+            (
+                self.ids[self.targets[edge] as usize],
+                self.symbols.resolve(self.edge_labels[edge]),
+            )
+        })
+    }
+
+    pub fn labels(&self, id: usize) -> impl Iterator<Item = &str> + '_ {
+        let range = match self.index_of(id) {
+            Some(index) => self.label_offsets[index]..self.label_offsets[index + 1],
+            None => 0..0,
+        };
+
+        self.node_labels[range]
+            .iter()
+            .map(|&label| self.symbols.resolve(label))
+    }
+
+    pub fn has_label(&self, id: usize, label: &str) -> bool {
+        self.labels(id).any(|l| l == label)
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+}
+
+impl From<&Graph> for CsrSnapshot {
+    fn from(graph: &Graph) -> Self {
+        let mut ids = graph.nodes().map(|node| node.id()).collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert!(
+            ids.len() < ABSENT as usize,
+            "graph too large for a CSR snapshot"
+        );
+
+        let mut indices = vec![ABSENT; graph.id_bound()];
+        for (index, &id) in ids.iter().enumerate() {
+            indices[id] = index as u32;
+        }
+
+        let mut offsets = Vec::with_capacity(ids.len() + 1);
+        let mut targets = Vec::with_capacity(graph.edge_count());
+        let mut edge_labels = Vec::with_capacity(graph.edge_count());
+        let mut label_offsets = Vec::with_capacity(ids.len() + 1);
+        let mut node_labels = Vec::new();
+
+        offsets.push(0);
+        label_offsets.push(0);
+        for &id in &ids {
+            for edge in graph.outgoing(id) {
+                targets.push(indices[edge.to()]);
+                edge_labels.push(edge.label());
+            }
+            offsets.push(targets.len());
+
+            let node = graph
+                .get_node(id)
+                .expect("ids are taken from the graph itself");
+            node_labels.extend_from_slice(node.labels());
+            label_offsets.push(node_labels.len());
+        }
+
+        Self {
+            ids,
+            indices,
+            offsets,
+            targets,
+            edge_labels,
+            label_offsets,
+            node_labels,
+            symbols: graph.symbols().clone(),
+        }
+    }
+}
+
+impl Topology for CsrSnapshot {
+    fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        self.index_of(id).is_some()
+    }
+
+    fn node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ids.iter().copied()
+    }
+
+    fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        let targets = match self.index_of(id) {
+            Some(index) => self.neighbor_indices(index),
+            None => &[],
+        };
+
+        targets.iter().map(|&target| self.ids[target as usize])
+    }
+
+    fn out_degree(&self, id: usize) -> usize {
+        self.index_of(id)
+            .map_or(0, |index| self.offsets[index + 1] - self.offsets[index])
+    }
+
+    fn as_csr(&self) -> Option<&CsrSnapshot> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::graph::graph::{Edge, Node};
+    use crate::graph::schema::Catalog;
+
+    /// Nodes 1, 4 and 7 of a graph whose other ids are unused.
+    fn gapped() -> Graph {
+        let mut symbols = SymbolTable::new();
+        let (person, admin) = (symbols.intern("person"), symbols.intern("admin"));
+        let (knows, follows) = (symbols.intern("knows"), symbols.intern("follows"));
+
+        let nodes = [(1, vec![person]), (4, vec![person, admin]), (7, vec![])]
+            .into_iter()
+            .map(|(id, labels)| (id, Node::new(id, labels, HashMap::new())))
+            .collect();
+        let edges = HashMap::from([
+            (1, vec![Edge::new(1, 7, knows), Edge::new(1, 4, follows)]),
+            (7, vec![Edge::new(7, 1, knows)]),
+        ]);
+
+        Graph::from_parts(nodes, edges, symbols, Catalog::default(), Vec::new(), 8).unwrap()
+    }
+
+    #[test]
+    fn ids_are_remapped_to_dense_indices_in_ascending_order() {
+        let csr = gapped().snapshot();
+
+        assert_eq!(csr.node_count(), 3);
+        assert_eq!(csr.edge_count(), 3);
+        assert_eq!(csr.node_ids().collect::<Vec<_>>(), [1, 4, 7]);
+        for (index, id) in [1, 4, 7].into_iter().enumerate() {
+            assert_eq!(csr.index_of(id), Some(index));
+            assert_eq!(csr.id_of(index), id);
+        }
+        for id in [0, 2, 3, 5, 6, 8, 100] {
+            assert_eq!(csr.index_of(id), None);
+            assert!(!csr.contains(id));
+        }
+    }
+
+    #[test]
+    fn edges_keep_their_targets_and_labels() {
+        let csr = gapped().snapshot();
+
+        assert_eq!(csr.neighbor_indices(0), [2, 1]);
+        assert_eq!(csr.get_neighbors(1), Some(vec![7, 4]));
+        assert_eq!(csr.get_neighbors(4), Some(Vec::new()));
+        assert_eq!(csr.get_neighbors(2), None);
+        assert_eq!(
+            csr.outgoing(1).collect::<Vec<_>>(),
+            [(7, "knows"), (4, "follows")]
+        );
+        assert_eq!(csr.outgoing(7).collect::<Vec<_>>(), [(1, "knows")]);
+        assert_eq!(csr.outgoing(3).count(), 0);
+        assert_eq!(csr.out_degree(1), 2);
+        assert_eq!(csr.out_degree(5), 0);
+    }
+
+    #[test]
+    fn nodes_keep_their_labels() {
+        let csr = gapped().snapshot();
+
+        assert_eq!(csr.labels(1).collect::<Vec<_>>(), ["person"]);
+        assert_eq!(csr.labels(4).collect::<Vec<_>>(), ["person", "admin"]);
+        assert_eq!(csr.labels(7).count(), 0);
+        assert_eq!(csr.labels(2).count(), 0);
+        assert!(csr.has_label(4, "admin"));
+        assert!(!csr.has_label(1, "admin"));
+        assert!(!csr.has_label(5, "person"));
+    }
+}
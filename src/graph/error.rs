@@ -0,0 +1,33 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::graph::constraint::ConstraintViolation;
+use crate::graph::schema::SchemaViolation;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    NodeNotFound(usize),
+    /// A write would break the schema; carries the offending node once it has an id.
+    Schema(Option<usize>, SchemaViolation),
+    /// A write would break a constraint; carries the offending node once it has an id.
+    Constraint(Option<usize>, ConstraintViolation),
+    /// Another transaction committed a write to this node after the failing one started.
+    WriteConflict(usize),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::NodeNotFound(id) => write!(f, "node {id} does not exist"),
+            GraphError::Schema(Some(id), violation) => write!(f, "node {id}: {violation}"),
+            GraphError::Schema(None, violation) => write!(f, "{violation}"),
+            GraphError::Constraint(Some(id), violation) => write!(f, "node {id}: {violation}"),
+            GraphError::Constraint(None, violation) => write!(f, "{violation}"),
+            GraphError::WriteConflict(id) => {
+                write!(f, "node {id} was changed by a concurrent transaction")
+            }
+        }
+    }
+}
+
+impl Error for GraphError {}
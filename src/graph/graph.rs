@@ -1,20 +1,98 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-pub(crate) struct Node {
+use crate::graph::constraint::{Constraint, Constraints};
+use crate::graph::csr::CsrSnapshot;
+use crate::graph::error::GraphError;
+use crate::graph::schema::{Catalog, PropertyDefinition};
+use crate::graph::symbols::{Symbol, SymbolTable};
+use crate::graph::topology::Topology;
+use crate::graph::transaction::Transaction;
+use crate::graph::value::Value;
+
+#[derive(Debug, Clone)]
+pub struct Node {
     id: usize,
-    labels: Vec<String>,
-    properties: HashMap<String, String>,
+    labels: Vec<Symbol>,
+    properties: HashMap<Symbol, Value>,
 }
 
-pub(crate) struct Edge {
+impl Node {
+    pub(crate) fn new(id: usize, labels: Vec<Symbol>, properties: HashMap<Symbol, Value>) -> Self {
+        Self {
+            id,
+            labels,
+            properties,
+        }
+    }
+
+    pub(crate) fn properties_mut(&mut self) -> &mut HashMap<Symbol, Value> {
+        &mut self.properties
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn labels(&self) -> &[Symbol] {
+        &self.labels
+    }
+
+    pub fn properties(&self) -> &HashMap<Symbol, Value> {
+        &self.properties
+    }
+
+    pub fn has_label(&self, label: Symbol) -> bool {
+        self.labels.contains(&label)
+    }
+
+    pub fn property(&self, key: Symbol) -> Option<&Value> {
+        self.properties.get(&key)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
     from: usize,
     to: usize,
-    label: String,
+    label: Symbol,
 }
 
-pub(crate) struct Graph {
+impl Edge {
+    pub(crate) fn new(from: usize, to: usize, label: Symbol) -> Self {
+        Self { from, to, label }
+    }
+
+    pub fn from(&self) -> usize {
+        self.from
+    }
+
+    pub fn to(&self) -> usize {
+        self.to
+    }
+
+    pub fn label(&self) -> Symbol {
+        self.label
+    }
+}
+
+/// Nodes and adjacency lists written since the changes were last taken, and whether the schemas
+/// or constraints changed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Changes {
+    pub nodes: BTreeSet<usize>,
+    pub edges: BTreeSet<usize>,
+    pub rules: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct Graph {
     nodes: HashMap<usize, Node>,
     edges: HashMap<usize, Vec<Edge>>,
+    symbols: SymbolTable,
+    catalog: Catalog,
+    constraints: Constraints,
+    /// Only tracked for graphs whose writes are published elsewhere.
+    changes: Option<Changes>,
     next_id: usize,
 }
 
@@ -23,13 +101,62 @@ impl Graph {
         Self {
             nodes: HashMap::new(),
             edges: HashMap::new(),
+            symbols: SymbolTable::new(),
+            catalog: Catalog::default(),
+            constraints: Constraints::default(),
+            changes: None,
             next_id: 0,
         }
     }
 
-    pub fn add_node(&mut self, labels: Vec<String>, properties: HashMap<String, String>) -> usize {
+    /// Assembles a graph from parts that are known to satisfy `catalog`, adding `constraints`.
+    pub(crate) fn from_parts(
+        nodes: HashMap<usize, Node>,
+        edges: HashMap<usize, Vec<Edge>>,
+        symbols: SymbolTable,
+        catalog: Catalog,
+        constraints: Vec<Constraint>,
+        next_id: usize,
+    ) -> Result<Self, GraphError> {
+        let mut graph = Self {
+            nodes,
+            edges,
+            symbols,
+            catalog,
+            next_id,
+            ..Self::new()
+        };
+        for constraint in constraints {
+            graph.create_constraint(constraint)?;
+        }
+
+        Ok(graph)
+    }
+
+    pub fn add_node(
+        &mut self,
+        labels: Vec<String>,
+        properties: HashMap<String, Value>,
+    ) -> Result<usize, GraphError> {
+        let labels = labels
+            .iter()
+            .map(|label| self.symbols.intern(label))
+            .collect::<Vec<_>>();
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (self.symbols.intern(&key), value))
+            .collect();
+
+        self.catalog
+            .validate_node(&self.symbols, &labels, &properties)
+            .map_err(|violation| GraphError::Schema(None, violation))?;
+        self.constraints
+            .check(None, &labels, &properties)
+            .map_err(|violation| GraphError::Constraint(None, violation))?;
+
         let id = self.next_id;
         self.next_id += 1;
+        self.constraints.insert(id, &labels, &properties);
 
         let node = Node {
             id,
@@ -38,75 +165,269 @@ impl Graph {
         };
 
         self.nodes.insert(id, node);
+        self.changed(|changes| changes.nodes.insert(id));
 
-        id
+        Ok(id)
     }
 
-    pub fn add_edge(&mut self, from: usize, to: usize, label: String) -> Result<(), ()> {
-        let (from_exists, to_exists) = (
-            self.nodes.get(&from).is_some(),
-            self.nodes.get(&to).is_some(),
-        );
+    pub fn add_edge(&mut self, from: usize, to: usize, label: String) -> Result<(), GraphError> {
+        let (Some(source), Some(target)) = (self.nodes.get(&from), self.nodes.get(&to)) else {
+            let missing = if self.nodes.contains_key(&from) {
+                to
+            } else {
+                from
+            };
+            return Err(GraphError::NodeNotFound(missing));
+        };
 
-        if !(from_exists && to_exists) {
-            return Err(());
-        }
+        let label = self.symbols.intern(&label);
+        self.catalog
+            .validate_edge(&self.symbols, label, &source.labels, &target.labels)
+            .map_err(|violation| GraphError::Schema(Some(from), violation))?;
 
         let edge = Edge { from, to, label };
 
-        let edges = self.edges.entry(from).or_insert(Vec::new());
+        let edges = self.edges.entry(from).or_default();
         edges.push(edge);
+        self.changed(|changes| changes.edges.insert(from));
+
+        Ok(())
+    }
+
+    /// Sets the property `key` of node `id`, returning the value it replaced.
+    pub fn set_property(
+        &mut self,
+        id: usize,
+        key: &str,
+        value: Value,
+    ) -> Result<Option<Value>, GraphError> {
+        let key = self.symbols.intern(key);
+        self.update_properties(id, |properties| properties.insert(key, value))
+    }
+
+    /// Removes the property `key` of node `id`, returning its value.
+    pub fn remove_property(&mut self, id: usize, key: &str) -> Result<Option<Value>, GraphError> {
+        let key = self.symbols.intern(key);
+        self.update_properties(id, |properties| properties.remove(&key))
+    }
+
+    fn update_properties(
+        &mut self,
+        id: usize,
+        update: impl FnOnce(&mut HashMap<Symbol, Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, GraphError> {
+        let node = self.nodes.get(&id).ok_or(GraphError::NodeNotFound(id))?;
+
+        let mut properties = node.properties.clone();
+        let previous = update(&mut properties);
+        self.catalog
+            .validate_node(&self.symbols, &node.labels, &properties)
+            .map_err(|violation| GraphError::Schema(Some(id), violation))?;
+        self.constraints
+            .check(Some(id), &node.labels, &properties)
+            .map_err(|violation| GraphError::Constraint(Some(id), violation))?;
+
+        let node = self.nodes.get_mut(&id).expect("existence checked above");
+        self.constraints.remove(id, &node.labels, &node.properties);
+        self.constraints.insert(id, &node.labels, &properties);
+        node.properties = properties;
+        self.changed(|changes| changes.nodes.insert(id));
+
+        Ok(previous)
+    }
+
+    /// Defines or replaces the schema of `label`.
+    ///
+    /// Fails without changing anything if an existing node with that label does not conform.
+    pub fn define_label(
+        &mut self,
+        label: &str,
+        properties: Vec<PropertyDefinition>,
+    ) -> Result<(), GraphError> {
+        let mut catalog = self.catalog.clone();
+        let label = catalog.define_label(&mut self.symbols, label, properties);
+
+        for node in self
+            .nodes_in_id_order()
+            .filter(|node| node.has_label(label))
+        {
+            catalog
+                .validate_node(&self.symbols, &node.labels, &node.properties)
+                .map_err(|violation| GraphError::Schema(Some(node.id), violation))?;
+        }
+
+        self.catalog = catalog;
+        self.changed(|changes| changes.rules = true);
 
         Ok(())
     }
 
+    /// Restricts edges labelled `label` to run from `from` nodes to `to` nodes.
+    ///
+    /// Fails without changing anything if an existing edge with that label does not conform.
+    pub fn define_edge(&mut self, label: &str, from: &str, to: &str) -> Result<(), GraphError> {
+        let mut catalog = self.catalog.clone();
+        let label = catalog.define_edge(&mut self.symbols, label, from, to);
+
+        for node in self.nodes_in_id_order() {
+            for edge in self.outgoing(node.id).iter().filter(|e| e.label == label) {
+                let target = &self.nodes[&edge.to];
+                catalog
+                    .validate_edge(&self.symbols, label, &node.labels, &target.labels)
+                    .map_err(|violation| GraphError::Schema(Some(node.id), violation))?;
+            }
+        }
+
+        self.catalog = catalog;
+        self.changed(|changes| changes.rules = true);
+
+        Ok(())
+    }
+
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Adds a constraint checked on every following write.
+    ///
+    /// Fails without changing anything if existing nodes already violate it.
+    pub fn create_constraint(&mut self, constraint: Constraint) -> Result<(), GraphError> {
+        let label = self.symbols.intern(constraint.label());
+        let property = self.symbols.intern(constraint.property());
+        let nodes = (0..self.next_id).filter_map(|id| self.nodes.get(&id));
+
+        self.constraints.add(constraint, label, property, nodes)?;
+        self.changed(|changes| changes.rules = true);
+
+        Ok(())
+    }
+
+    pub fn constraints(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter()
+    }
+
+    /// Constraints together with the indexes of the unique ones.
+    pub(crate) fn constraint_index(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    /// Starts a [`Transaction`] staging its writes on a copy of the graph, which replaces the
+    /// graph only once the transaction commits. Beginning takes time linear in the graph's size.
+    pub fn begin(&mut self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Starts recording which parts of the graph every write changes.
+    pub(crate) fn track_changes(&mut self) {
+        self.changes.get_or_insert_default();
+    }
+
+    /// Changes recorded since the last call, if they are tracked at all.
+    pub(crate) fn take_changes(&mut self) -> Changes {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn changed<T>(&mut self, record: impl FnOnce(&mut Changes) -> T) {
+        if let Some(changes) = &mut self.changes {
+            record(changes);
+        }
+    }
+
+    fn nodes_in_id_order(&self) -> impl Iterator<Item = &Node> {
+        (0..self.next_id).filter_map(|id| self.nodes.get(&id))
+    }
+
     pub fn get_node(&self, id: usize) -> Option<&Node> {
         self.nodes.get(&id)
     }
 
+    /// Table resolving the [`Symbol`]s stored in nodes and edges back to strings.
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn nodes_with_label(&self, label: &str) -> impl Iterator<Item = &Node> {
+        let label = self.symbols.get(label);
+        self.nodes
+            .values()
+            .filter(move |node| label.is_some_and(|label| node.has_label(label)))
+    }
+
+    /// Nodes with `label` whose string value of `property` starts with `prefix`, found through
+    /// the ordered index of a unique constraint on the property. Nodes holding a list in the
+    /// property come along regardless of its elements. `None` if there is no such index.
+    pub fn nodes_with_prefix<'g, 'p>(
+        &'g self,
+        label: &str,
+        property: &str,
+        prefix: &'p str,
+    ) -> Option<impl Iterator<Item = &'g Node> + use<'g, 'p>> {
+        let ids = self.constraints.prefixed(
+            self.symbols.get(label)?,
+            self.symbols.get(property)?,
+            prefix,
+        )?;
+
+        Some(ids.map(|id| &self.nodes[&id]))
+    }
+
+    /// Value of the property `key` of node `id`.
+    pub fn property(&self, id: usize, key: &str) -> Option<&Value> {
+        self.nodes.get(&id)?.property(self.symbols.get(key)?)
+    }
+
     pub fn get_neighbors(&self, id: usize) -> Option<Vec<usize>> {
         Some(self.edges.get(&id)?.iter().map(|e| e.to).collect())
     }
 
-    pub fn dfs(&self, from: usize, to: usize) -> Option<Vec<usize>> {
-        let mut visited = HashMap::new();
-        let mut path = Vec::new();
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.values()
+    }
 
-        if !self.m_dfs(from, to, &mut visited, &mut path) {
-            return None;
-        }
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.values().flatten()
+    }
 
-        Some(path)
+    pub fn outgoing(&self, id: usize) -> &[Edge] {
+        self.edges.get(&id).map_or(&[], Vec::as_slice)
     }
 
-    fn m_dfs(
-        &self,
-        current: usize,
-        to: usize,
-        visited: &mut HashMap<usize, bool>,
-        path: &mut Vec<usize>,
-    ) -> bool {
-        if visited.contains_key(&current) {
-            return false;
-        }
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
 
-        visited.insert(current, true);
-        path.push(current);
+    pub fn edge_count(&self) -> usize {
+        self.edges.values().map(Vec::len).sum()
+    }
 
-        if current == to {
-            return true;
-        }
+    /// Upper bound (exclusive) of every node id handed out so far.
+    pub fn id_bound(&self) -> usize {
+        self.next_id
+    }
 
-        if let Some(edges) = self.edges.get(&current) {
-            for edge in edges {
-                if self.m_dfs(edge.to, to, visited, path) {
-                    return true;
-                }
-            }
-        }
+    /// Freezes the current state into a read-only [`CsrSnapshot`] for whole-graph algorithms.
+    pub fn snapshot(&self) -> CsrSnapshot {
+        CsrSnapshot::from(self)
+    }
+}
+
+impl Topology for Graph {
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
 
-        path.pop();
+    fn contains(&self, id: usize) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    fn node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.nodes.keys().copied()
+    }
 
-        false
+    fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.outgoing(id).iter().map(|e| e.to)
     }
 }
@@ -1 +1,27 @@
+pub mod analytics;
+mod constraint;
+mod csr;
+mod error;
+#[allow(clippy::module_inception)]
 mod graph;
+pub mod mvcc;
+mod schema;
+mod shared;
+mod symbols;
+mod topology;
+mod transaction;
+mod value;
+
+pub(crate) use constraint::IndexKey;
+pub use constraint::{Constraint, ConstraintViolation};
+pub use csr::CsrSnapshot;
+pub use error::GraphError;
+pub use graph::{Edge, Graph, Node};
+pub use schema::{
+    Catalog, EdgeSchema, LabelSchema, PropertyDefinition, PropertyType, RECORD_ID, SchemaViolation,
+};
+pub use shared::SharedGraph;
+pub use symbols::{Symbol, SymbolTable};
+pub use topology::Topology;
+pub use transaction::Transaction;
+pub use value::Value;
@@ -0,0 +1,998 @@
+//! Multi-version store letting readers work on a consistent snapshot while writers commit.
+//!
+//! Every node and every adjacency list keeps a chain of versions stamped with the commit
+//! timestamp that produced them. A [`Snapshot`] pins the timestamp it was opened at and only sees
+//! versions committed up to then; a [`WriteTransaction`] stages its writes privately and
+//! publishes them atomically under a new timestamp. Writers never wait for readers and readers
+//! never see a half-applied commit.
+//!
+//! Two transactions writing the same node or adjacency list conflict: the first to commit wins
+//! and the other fails with [`GraphError::WriteConflict`], so no update is silently lost.
+//!
+//! A store made from a [`Graph`] takes over its schemas and constraints and checks every commit
+//! against them. A [`Snapshot`] converts back into a [`Graph`] with the schemas and constraints
+//! in force at its timestamp. The store also serves as the read side of a
+//! [`SharedGraph`](crate::graph::SharedGraph), which publishes every write of its graph here.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::graph::constraint::{Constraint, Constraints};
+use crate::graph::error::GraphError;
+use crate::graph::graph::{Changes, Edge, Graph, Node};
+use crate::graph::schema::Catalog;
+use crate::graph::symbols::{Symbol, SymbolTable};
+use crate::graph::topology::Topology;
+use crate::graph::value::Value;
+
+/// Number of commits between automatic garbage collection runs.
+const GC_INTERVAL: u64 = 256;
+
+struct Version<T> {
+    begin: u64,
+    data: Arc<T>,
+}
+
+type Chain<T> = Vec<Version<T>>;
+
+/// Newest version committed at or before `timestamp`.
+fn visible<T>(chain: &Chain<T>, timestamp: u64) -> Option<&Arc<T>> {
+    chain
+        .iter()
+        .rev()
+        .find(|version| version.begin <= timestamp)
+        .map(|version| &version.data)
+}
+
+/// Whether a version newer than `timestamp` was committed.
+fn changed_since<T>(chain: Option<&Chain<T>>, timestamp: u64) -> bool {
+    chain
+        .and_then(|chain| chain.last())
+        .is_some_and(|version| version.begin > timestamp)
+}
+
+/// Drops every version hidden behind a newer one that is visible at `horizon`.
+fn prune<T>(chain: &mut Chain<T>, horizon: u64) -> usize {
+    let keep_from = chain
+        .iter()
+        .rposition(|version| version.begin <= horizon)
+        .unwrap_or(0);
+
+    chain.drain(..keep_from).count()
+}
+
+fn version<T>(begin: u64, data: T) -> Version<T> {
+    Version {
+        begin,
+        data: Arc::new(data),
+    }
+}
+
+/// Schemas and constraints in force as of a commit.
+#[derive(Default)]
+struct Rules {
+    catalog: Catalog,
+    constraints: Vec<Constraint>,
+}
+
+struct State {
+    clock: u64,
+    nodes: HashMap<usize, Chain<Node>>,
+    edges: HashMap<usize, Chain<Vec<Edge>>>,
+    rules: Chain<Rules>,
+    /// Constraints of the latest commit, indexing its nodes.
+    index: Constraints,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            clock: 0,
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            rules: vec![version(0, Rules::default())],
+            index: Constraints::default(),
+        }
+    }
+}
+
+impl State {
+    fn node(&self, id: usize, timestamp: u64) -> Option<&Arc<Node>> {
+        visible(self.nodes.get(&id)?, timestamp)
+    }
+
+    fn edges(&self, id: usize, timestamp: u64) -> Option<&Arc<Vec<Edge>>> {
+        visible(self.edges.get(&id)?, timestamp)
+    }
+
+    fn rules(&self, timestamp: u64) -> &Arc<Rules> {
+        visible(&self.rules, timestamp).expect("the first rules are committed at timestamp 0")
+    }
+}
+
+/// Graph store with multi-version concurrency control, shareable between threads.
+#[derive(Default)]
+pub struct MvccGraph {
+    state: RwLock<State>,
+    /// Number of open snapshots per pinned timestamp.
+    readers: Mutex<BTreeMap<u64, usize>>,
+    symbols: RwLock<SymbolTable>,
+    next_id: AtomicUsize,
+}
+
+impl MvccGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a read-only view of the latest committed state.
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        Snapshot {
+            graph: self,
+            timestamp: self.pin(),
+        }
+    }
+
+    /// Starts a write transaction reading from the latest committed state.
+    pub fn begin(&self) -> WriteTransaction<'_> {
+        WriteTransaction {
+            graph: self,
+            start: self.pin(),
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            created: HashSet::new(),
+            finished: false,
+        }
+    }
+
+    /// Timestamp of the latest commit.
+    pub fn timestamp(&self) -> u64 {
+        self.state().clock
+    }
+
+    /// Total number of node and adjacency versions currently retained.
+    pub fn version_count(&self) -> usize {
+        let state = self.state();
+        state.nodes.values().map(Vec::len).sum::<usize>()
+            + state.edges.values().map(Vec::len).sum::<usize>()
+    }
+
+    /// Drops versions that no open snapshot or transaction can see anymore, returning how many
+    /// were removed. Also runs on its own every few hundred commits.
+    pub fn collect_garbage(&self) -> usize {
+        let mut state = self.state_mut();
+        let horizon = self.readers().keys().next().copied().unwrap_or(state.clock);
+
+        let nodes = state
+            .nodes
+            .values_mut()
+            .map(|chain| prune(chain, horizon))
+            .sum::<usize>();
+        let edges = state
+            .edges
+            .values_mut()
+            .map(|chain| prune(chain, horizon))
+            .sum::<usize>();
+        prune(&mut state.rules, horizon);
+
+        nodes + edges
+    }
+
+    /// Commits the writes `changes` recorded on `graph` under a new timestamp, which is returned.
+    ///
+    /// The graph has checked its schemas and constraints already, so nothing is checked again.
+    /// It must be the graph the store was made from, and the only one writing to it.
+    pub(crate) fn publish(&self, graph: &Graph, changes: Changes) -> u64 {
+        if changes.nodes.is_empty() && changes.edges.is_empty() && !changes.rules {
+            return self.timestamp();
+        }
+
+        // symbols are only ever added, so the graph's table extends the published one
+        {
+            let mut symbols = self.symbols.write().expect("symbol table lock poisoned");
+            if symbols.len() < graph.symbols().len() {
+                *symbols = graph.symbols().clone();
+            }
+        }
+        self.next_id.fetch_max(graph.id_bound(), Ordering::Relaxed);
+
+        let timestamp = {
+            let mut state = self.state_mut();
+            state.clock += 1;
+            let timestamp = state.clock;
+
+            for id in changes.nodes {
+                let Some(node) = graph.get_node(id) else {
+                    continue;
+                };
+                if !changes.rules {
+                    if let Some(previous) = state.node(id, timestamp).cloned() {
+                        state
+                            .index
+                            .remove(id, previous.labels(), previous.properties());
+                    }
+                    state.index.insert(id, node.labels(), node.properties());
+                }
+                state
+                    .nodes
+                    .entry(id)
+                    .or_default()
+                    .push(version(timestamp, node.clone()));
+            }
+
+            for id in changes.edges {
+                let edges = graph.outgoing(id).to_vec();
+                state
+                    .edges
+                    .entry(id)
+                    .or_default()
+                    .push(version(timestamp, edges));
+            }
+
+            if changes.rules {
+                state.rules.push(version(timestamp, Rules::of(graph)));
+                state.index = graph.constraint_index().clone();
+            }
+
+            timestamp
+        };
+
+        self.committed(timestamp);
+        timestamp
+    }
+
+    /// Collects garbage every few hundred commits.
+    fn committed(&self, timestamp: u64) {
+        if timestamp.is_multiple_of(GC_INTERVAL) {
+            self.collect_garbage();
+        }
+    }
+
+    pub fn symbols(&self) -> RwLockReadGuard<'_, SymbolTable> {
+        self.symbols.read().expect("symbol table lock poisoned")
+    }
+
+    /// Registers a reader at the current timestamp.
+    ///
+    /// The state lock is held while registering so garbage collection cannot slip in between
+    /// reading the clock and protecting it.
+    fn pin(&self) -> u64 {
+        let state = self.state();
+        *self.readers().entry(state.clock).or_default() += 1;
+
+        state.clock
+    }
+
+    fn unpin(&self, timestamp: u64) {
+        let mut readers = self.readers();
+        if let Some(count) = readers.get_mut(&timestamp) {
+            *count -= 1;
+            if *count == 0 {
+                readers.remove(&timestamp);
+            }
+        }
+    }
+
+    fn state(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().expect("mvcc state lock poisoned")
+    }
+
+    fn state_mut(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().expect("mvcc state lock poisoned")
+    }
+
+    fn readers(&self) -> MutexGuard<'_, BTreeMap<u64, usize>> {
+        self.readers.lock().expect("reader registry lock poisoned")
+    }
+}
+
+impl Rules {
+    fn of(graph: &Graph) -> Self {
+        Self {
+            catalog: graph.catalog().clone(),
+            constraints: graph.constraints().cloned().collect(),
+        }
+    }
+}
+
+impl From<&Graph> for MvccGraph {
+    /// Imports the current state of `graph` as the first version, together with its schemas and
+    /// constraints.
+    fn from(graph: &Graph) -> Self {
+        let state = State {
+            clock: 0,
+            nodes: graph
+                .nodes()
+                .map(|node| (node.id(), vec![version(0, node.clone())]))
+                .collect(),
+            edges: graph
+                .node_ids()
+                .filter(|&id| !graph.outgoing(id).is_empty())
+                .map(|id| (id, vec![version(0, graph.outgoing(id).to_vec())]))
+                .collect(),
+            rules: vec![version(0, Rules::of(graph))],
+            index: graph.constraint_index().clone(),
+        };
+
+        Self {
+            state: RwLock::new(state),
+            readers: Mutex::default(),
+            symbols: RwLock::new(graph.symbols().clone()),
+            next_id: AtomicUsize::new(graph.id_bound()),
+        }
+    }
+}
+
+/// Consistent read-only view of an [`MvccGraph`] as of one commit.
+///
+/// Versions visible to an open snapshot survive garbage collection until it is dropped.
+pub struct Snapshot<'g> {
+    graph: &'g MvccGraph,
+    timestamp: u64,
+}
+
+impl Snapshot<'_> {
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_node(&self, id: usize) -> Option<Arc<Node>> {
+        self.graph.state().node(id, self.timestamp).cloned()
+    }
+
+    pub fn get_neighbors(&self, id: usize) -> Option<Vec<usize>> {
+        let state = self.graph.state();
+        let edges = state.edges(id, self.timestamp)?;
+
+        Some(edges.iter().map(Edge::to).collect())
+    }
+
+    pub fn outgoing(&self, id: usize) -> Arc<Vec<Edge>> {
+        self.graph
+            .state()
+            .edges(id, self.timestamp)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Value of the property `key` of node `id`.
+    pub fn property(&self, id: usize, key: &str) -> Option<Value> {
+        let key = self.graph.symbols().get(key)?;
+        self.get_node(id)?.property(key).cloned()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        let state = self.graph.state();
+        state
+            .edges
+            .values()
+            .filter_map(|chain| visible(chain, self.timestamp))
+            .map(|edges| edges.len())
+            .sum()
+    }
+
+    /// Copies the snapshot into a standalone [`Graph`] with the schemas and constraints in force
+    /// at its timestamp.
+    pub fn to_graph(&self) -> Graph {
+        let state = self.graph.state();
+        let nodes = state
+            .nodes
+            .iter()
+            .filter_map(|(&id, chain)| Some((id, visible(chain, self.timestamp)?.as_ref().clone())))
+            .collect();
+        let edges = state
+            .edges
+            .iter()
+            .filter_map(|(&id, chain)| Some((id, visible(chain, self.timestamp)?.as_ref().clone())))
+            .filter(|(_, edges)| !edges.is_empty())
+            .collect();
+        let rules = state.rules(self.timestamp);
+
+        Graph::from_parts(
+            nodes,
+            edges,
+            self.graph.symbols().clone(),
+            rules.catalog.clone(),
+            rules.constraints.clone(),
+            self.graph.next_id.load(Ordering::Relaxed),
+        )
+        .expect("every commit satisfies the constraints in force at it")
+    }
+
+    pub fn labels(&self, id: usize) -> Vec<String> {
+        let Some(node) = self.get_node(id) else {
+            return Vec::new();
+        };
+        let symbols = self.graph.symbols();
+
+        node.labels()
+            .iter()
+            .map(|&label| symbols.resolve(label).to_string())
+            .collect()
+    }
+}
+
+impl Topology for Snapshot<'_> {
+    fn node_count(&self) -> usize {
+        let state = self.graph.state();
+        state
+            .nodes
+            .values()
+            .filter(|chain| visible(chain, self.timestamp).is_some())
+            .count()
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        self.graph.state().node(id, self.timestamp).is_some()
+    }
+
+    fn node_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        let state = self.graph.state();
+        let mut ids = state
+            .nodes
+            .iter()
+            .filter(|(_, chain)| visible(chain, self.timestamp).is_some())
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+
+        ids.into_iter()
+    }
+
+    fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.get_neighbors(id).unwrap_or_default().into_iter()
+    }
+}
+
+impl Drop for Snapshot<'_> {
+    fn drop(&mut self) {
+        self.graph.unpin(self.timestamp);
+    }
+}
+
+/// Staged writes against an [`MvccGraph`], invisible to everyone else until committed.
+///
+/// Reads through the transaction see the state it started from plus its own writes. Dropping it
+/// without committing discards the writes.
+pub struct WriteTransaction<'g> {
+    graph: &'g MvccGraph,
+    start: u64,
+    nodes: HashMap<usize, Node>,
+    edges: HashMap<usize, Vec<Edge>>,
+    created: HashSet<usize>,
+    finished: bool,
+}
+
+impl WriteTransaction<'_> {
+    pub fn get_node(&self, id: usize) -> Option<Arc<Node>> {
+        match self.nodes.get(&id) {
+            Some(node) => Some(Arc::new(node.clone())),
+            None => self.graph.state().node(id, self.start).cloned(),
+        }
+    }
+
+    pub fn get_neighbors(&self, id: usize) -> Option<Vec<usize>> {
+        match self.edges.get(&id) {
+            Some(edges) => Some(edges.iter().map(Edge::to).collect()),
+            None => {
+                let state = self.graph.state();
+                Some(state.edges(id, self.start)?.iter().map(Edge::to).collect())
+            }
+        }
+    }
+
+    /// Value of the property `key` of node `id`.
+    pub fn property(&self, id: usize, key: &str) -> Option<Value> {
+        let key = self.graph.symbols().get(key)?;
+        self.get_node(id)?.property(key).cloned()
+    }
+
+    pub fn add_node(&mut self, labels: Vec<String>, properties: HashMap<String, Value>) -> usize {
+        let id = self.graph.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut symbols = self
+            .graph
+            .symbols
+            .write()
+            .expect("symbol table lock poisoned");
+        let labels = labels.iter().map(|label| symbols.intern(label)).collect();
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (symbols.intern(&key), value))
+            .collect();
+
+        self.nodes.insert(id, Node::new(id, labels, properties));
+        self.created.insert(id);
+
+        id
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, label: String) -> Result<(), GraphError> {
+        for id in [from, to] {
+            if self.get_node(id).is_none() {
+                return Err(GraphError::NodeNotFound(id));
+            }
+        }
+
+        let label = self
+            .graph
+            .symbols
+            .write()
+            .expect("symbol table lock poisoned")
+            .intern(&label);
+        self.staged_edges(from).push(Edge::new(from, to, label));
+
+        Ok(())
+    }
+
+    /// Sets the property `key` of node `id`, returning the value it replaced.
+    pub fn set_property(
+        &mut self,
+        id: usize,
+        key: &str,
+        value: Value,
+    ) -> Result<Option<Value>, GraphError> {
+        let key = self
+            .graph
+            .symbols
+            .write()
+            .expect("symbol table lock poisoned")
+            .intern(key);
+
+        Ok(self.staged_node(id)?.properties_mut().insert(key, value))
+    }
+
+    /// Removes the property `key` of node `id`, returning its value.
+    pub fn remove_property(&mut self, id: usize, key: &str) -> Result<Option<Value>, GraphError> {
+        let Some(key) = self.graph.symbols().get(key) else {
+            return Ok(None);
+        };
+
+        Ok(self.staged_node(id)?.properties_mut().remove(&key))
+    }
+
+    /// Publishes every staged write under a new timestamp, which is returned.
+    ///
+    /// Fails without publishing anything if another transaction committed a write to the same
+    /// node or adjacency list first, or if the writes break a schema or constraint.
+    pub fn commit(mut self) -> Result<u64, GraphError> {
+        self.finished = true;
+        self.graph.unpin(self.start);
+
+        let timestamp = {
+            let mut state = self.graph.state_mut();
+
+            for &id in self.nodes.keys().filter(|id| !self.created.contains(id)) {
+                if changed_since(state.nodes.get(&id), self.start) {
+                    return Err(GraphError::WriteConflict(id));
+                }
+            }
+
+            for &id in self.edges.keys() {
+                if changed_since(state.edges.get(&id), self.start) {
+                    return Err(GraphError::WriteConflict(id));
+                }
+            }
+
+            self.check_schemas(&state)?;
+            self.index(&mut state)?;
+
+            state.clock += 1;
+            let timestamp = state.clock;
+
+            for (id, node) in self.nodes.drain() {
+                state
+                    .nodes
+                    .entry(id)
+                    .or_default()
+                    .push(version(timestamp, node));
+            }
+
+            for (id, edges) in self.edges.drain() {
+                state
+                    .edges
+                    .entry(id)
+                    .or_default()
+                    .push(version(timestamp, edges));
+            }
+
+            timestamp
+        };
+
+        self.graph.committed(timestamp);
+        Ok(timestamp)
+    }
+
+    pub fn rollback(self) {}
+
+    /// Checks the staged nodes and edges against the schemas of the latest commit.
+    fn check_schemas(&self, state: &State) -> Result<(), GraphError> {
+        let symbols = self.graph.symbols();
+        let catalog = &state.rules(state.clock).catalog;
+        let labels = |id: usize| -> Vec<Symbol> {
+            match self.nodes.get(&id) {
+                Some(node) => node.labels().to_vec(),
+                None => state
+                    .node(id, state.clock)
+                    .expect("edges only connect existing nodes")
+                    .labels()
+                    .to_vec(),
+            }
+        };
+
+        for id in self.staged_ids() {
+            let node = &self.nodes[&id];
+            catalog
+                .validate_node(&symbols, node.labels(), node.properties())
+                .map_err(|violation| GraphError::Schema(Some(id), violation))?;
+        }
+
+        for (&from, edges) in &self.edges {
+            for edge in edges {
+                catalog
+                    .validate_edge(&symbols, edge.label(), &labels(from), &labels(edge.to()))
+                    .map_err(|violation| GraphError::Schema(Some(from), violation))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves the staged nodes into the constraint index, checking them on the way. On a
+    /// violation, the index is left as it was.
+    fn index(&self, state: &mut State) -> Result<(), GraphError> {
+        let latest = state.clock;
+        let mut indexed = Vec::new();
+
+        for id in self.staged_ids() {
+            let node = &self.nodes[&id];
+            let previous = state.node(id, latest).cloned();
+            if let Some(previous) = &previous {
+                state
+                    .index
+                    .remove(id, previous.labels(), previous.properties());
+            }
+
+            let checked = state
+                .index
+                .check(Some(id), node.labels(), node.properties());
+            if let Err(violation) = checked {
+                indexed.push((id, previous));
+                for (id, previous) in indexed.into_iter().rev() {
+                    let node = &self.nodes[&id];
+                    state.index.remove(id, node.labels(), node.properties());
+                    if let Some(previous) = previous {
+                        state
+                            .index
+                            .insert(id, previous.labels(), previous.properties());
+                    }
+                }
+
+                return Err(GraphError::Constraint(Some(id), violation));
+            }
+
+            state.index.insert(id, node.labels(), node.properties());
+            indexed.push((id, previous));
+        }
+
+        Ok(())
+    }
+
+    /// Ids of the staged nodes in ascending order, so failures do not depend on hashing.
+    fn staged_ids(&self) -> Vec<usize> {
+        let mut ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn staged_node(&mut self, id: usize) -> Result<&mut Node, GraphError> {
+        if !self.nodes.contains_key(&id) {
+            let node = self
+                .graph
+                .state()
+                .node(id, self.start)
+                .ok_or(GraphError::NodeNotFound(id))?
+                .as_ref()
+                .clone();
+            self.nodes.insert(id, node);
+        }
+
+        Ok(self.nodes.get_mut(&id).expect("staged above"))
+    }
+
+    fn staged_edges(&mut self, id: usize) -> &mut Vec<Edge> {
+        self.edges.entry(id).or_insert_with(|| {
+            self.graph
+                .state()
+                .edges(id, self.start)
+                .map(|edges| edges.as_ref().clone())
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for WriteTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.graph.unpin(self.start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+    use crate::graph::{ConstraintViolation, PropertyDefinition, PropertyType, SchemaViolation};
+
+    trait Read {
+        fn property(&self, id: usize, key: &str) -> Option<Value>;
+    }
+
+    impl Read for Snapshot<'_> {
+        fn property(&self, id: usize, key: &str) -> Option<Value> {
+            Snapshot::property(self, id, key)
+        }
+    }
+
+    impl Read for WriteTransaction<'_> {
+        fn property(&self, id: usize, key: &str) -> Option<Value> {
+            WriteTransaction::property(self, id, key)
+        }
+    }
+
+    fn int(value: i64) -> HashMap<String, Value> {
+        HashMap::from([("n".to_string(), Value::Int(value))])
+    }
+
+    /// The `n` of node `id` as seen by a snapshot or transaction.
+    fn read(view: &impl Read, id: usize) -> i64 {
+        match view.property(id, "n") {
+            Some(Value::Int(n)) => n,
+            other => panic!("node {id} holds {other:?}"),
+        }
+    }
+
+    /// Moves one unit from `from` to `to`, retrying on conflicts.
+    fn transfer(graph: &MvccGraph, from: usize, to: usize) {
+        loop {
+            let mut transaction = graph.begin();
+            let (a, b) = (read(&transaction, from), read(&transaction, to));
+            transaction
+                .set_property(from, "n", Value::Int(a - 1))
+                .unwrap();
+            transaction
+                .set_property(to, "n", Value::Int(b + 1))
+                .unwrap();
+
+            match transaction.commit() {
+                Ok(_) => return,
+                Err(GraphError::WriteConflict(_)) => continue,
+                Err(error) => panic!("transfer failed: {error}"),
+            }
+        }
+    }
+
+    fn accounts(count: usize, balance: i64) -> MvccGraph {
+        let graph = MvccGraph::new();
+        let mut transaction = graph.begin();
+        for _ in 0..count {
+            transaction.add_node(vec!["account".to_string()], int(balance));
+        }
+        transaction.commit().unwrap();
+        graph
+    }
+
+    #[test]
+    fn snapshots_stay_consistent_while_writers_commit() {
+        const WRITERS: usize = 4;
+        const TRANSFERS: usize = 200;
+        let graph = accounts(2, 1000);
+
+        thread::scope(|scope| {
+            for writer in 0..WRITERS {
+                let graph = &graph;
+                scope.spawn(move || {
+                    for _ in 0..TRANSFERS {
+                        // half the writers move units each way, so the two nodes keep conflicting
+                        match writer % 2 {
+                            0 => transfer(graph, 0, 1),
+                            _ => transfer(graph, 1, 0),
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..2 {
+                let graph = &graph;
+                scope.spawn(move || {
+                    for _ in 0..200 {
+                        let snapshot = graph.snapshot();
+                        let first = (read(&snapshot, 0), read(&snapshot, 1));
+                        assert_eq!(first.0 + first.1, 2000, "a half-applied commit was visible");
+                        thread::yield_now();
+                        // commits made meanwhile stay invisible to the snapshot
+                        assert_eq!((read(&snapshot, 0), read(&snapshot, 1)), first);
+                    }
+                });
+            }
+        });
+
+        let snapshot = graph.snapshot();
+        assert_eq!(read(&snapshot, 0), 1000);
+        assert_eq!(read(&snapshot, 1), 1000);
+        assert_eq!(snapshot.timestamp(), 1 + (WRITERS * TRANSFERS) as u64);
+    }
+
+    #[test]
+    fn concurrent_updates_of_a_node_conflict() {
+        let graph = accounts(1, 0);
+        let barrier = Barrier::new(2);
+
+        let results = thread::scope(|scope| {
+            let handles = (0..2)
+                .map(|value| {
+                    let (graph, barrier) = (&graph, &barrier);
+                    scope.spawn(move || {
+                        let mut transaction = graph.begin();
+                        transaction.set_property(0, "n", Value::Int(value)).unwrap();
+                        // both have read the node before either commits
+                        barrier.wait();
+                        transaction.commit()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.contains(&Err(GraphError::WriteConflict(0))));
+        assert_eq!(graph.timestamp(), 2);
+    }
+
+    #[test]
+    fn retried_increments_are_never_lost() {
+        const THREADS: i64 = 8;
+        const INCREMENTS: i64 = 100;
+        let graph = accounts(1, 0);
+
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..INCREMENTS {
+                        loop {
+                            let mut transaction = graph.begin();
+                            let n = read(&transaction, 0);
+                            transaction.set_property(0, "n", Value::Int(n + 1)).unwrap();
+                            if transaction.commit().is_ok() {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(read(&graph.snapshot(), 0), THREADS * INCREMENTS);
+    }
+
+    #[test]
+    fn garbage_collection_keeps_versions_pinned_by_snapshots() {
+        let graph = accounts(1, 0);
+        let pinned = graph.snapshot();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for n in 1..=10 {
+                    let mut transaction = graph.begin();
+                    transaction.set_property(0, "n", Value::Int(n)).unwrap();
+                    transaction.commit().unwrap();
+                }
+                graph.collect_garbage();
+            });
+        });
+
+        // the version the snapshot sees and every newer one survive
+        assert_eq!(read(&pinned, 0), 0);
+        assert_eq!(read(&graph.snapshot(), 0), 10);
+        assert_eq!(graph.version_count(), 11);
+
+        drop(pinned);
+        assert_eq!(graph.collect_garbage(), 10);
+        assert_eq!(graph.version_count(), 1);
+        assert_eq!(read(&graph.snapshot(), 0), 10);
+    }
+
+    #[test]
+    fn commits_are_checked_against_the_graph_rules() {
+        let mut source = Graph::new();
+        source
+            .define_label(
+                "account",
+                vec![PropertyDefinition::new("n", PropertyType::Int, true)],
+            )
+            .unwrap();
+        source
+            .create_constraint(Constraint::Unique {
+                label: "account".to_string(),
+                property: "n".to_string(),
+            })
+            .unwrap();
+        source
+            .add_node(vec!["account".to_string()], int(1))
+            .unwrap();
+        let graph = MvccGraph::from(&source);
+
+        let mut transaction = graph.begin();
+        let id = transaction.add_node(vec!["account".to_string()], HashMap::new());
+        assert!(matches!(
+            transaction.commit(),
+            Err(GraphError::Schema(Some(failed), SchemaViolation::MissingProperty { .. })) if failed == id
+        ));
+
+        let mut transaction = graph.begin();
+        let id = transaction.add_node(vec!["account".to_string()], int(1));
+        assert!(matches!(
+            transaction.commit(),
+            Err(GraphError::Constraint(Some(failed), ConstraintViolation::Duplicate { existing: 0, .. })) if failed == id
+        ));
+
+        // a failed commit leaves the index as it was
+        let mut transaction = graph.begin();
+        transaction.set_property(0, "n", Value::Int(2)).unwrap();
+        transaction.add_node(vec!["account".to_string()], int(2));
+        assert!(transaction.commit().is_err());
+        let mut transaction = graph.begin();
+        transaction.add_node(vec!["account".to_string()], int(2));
+        assert!(transaction.commit().is_ok());
+    }
+
+    #[test]
+    fn snapshots_convert_back_into_graphs() {
+        let mut source = Graph::new();
+        source
+            .create_constraint(Constraint::Unique {
+                label: "account".to_string(),
+                property: "n".to_string(),
+            })
+            .unwrap();
+        source
+            .add_node(vec!["account".to_string()], int(1))
+            .unwrap();
+        let graph = MvccGraph::from(&source);
+
+        // a rolled back transaction leaves a gap in the ids
+        graph.begin().add_node(vec!["account".to_string()], int(2));
+        let mut transaction = graph.begin();
+        let id = transaction.add_node(vec!["account".to_string()], int(3));
+        transaction.add_edge(0, id, "owes".to_string()).unwrap();
+        transaction.commit().unwrap();
+        let before = graph.snapshot();
+        let mut transaction = graph.begin();
+        transaction.set_property(0, "n", Value::Int(4)).unwrap();
+        transaction.commit().unwrap();
+
+        let mut converted = before.to_graph();
+        assert_eq!(id, 2);
+        assert_eq!(converted.node_count(), 2);
+        assert_eq!(converted.get_neighbors(0), Some(vec![2]));
+        assert_eq!(converted.property(0, "n"), Some(&Value::Int(1)));
+        assert_eq!(converted.constraints().count(), 1);
+        assert!(
+            converted
+                .add_node(vec!["account".to_string()], int(3))
+                .is_err()
+        );
+        assert_eq!(converted.add_node(Vec::new(), HashMap::new()), Ok(3));
+        assert_eq!(
+            graph.snapshot().to_graph().property(0, "n"),
+            Some(&Value::Int(4))
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::graph::symbols::{Symbol, SymbolTable};
+use crate::graph::value::Value;
+
+/// Property holding the record id of `label:id` references.
+///
+/// Every node may carry it regardless of the schema of its labels.
+pub const RECORD_ID: &str = "id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    String,
+    Int,
+    Float,
+    Bool,
+    List,
+    Map,
+}
+
+impl PropertyType {
+    /// Whether `value` may be stored in a property of this type. Ints widen to floats.
+    pub fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (PropertyType::String, Value::String(_))
+                | (PropertyType::Int, Value::Int(_))
+                | (PropertyType::Float, Value::Float(_) | Value::Int(_))
+                | (PropertyType::Bool, Value::Bool(_))
+                | (PropertyType::List, Value::List(_))
+                | (PropertyType::Map, Value::Map(_))
+        )
+    }
+}
+
+impl fmt::Display for PropertyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PropertyType::String => "string",
+            PropertyType::Int => "int",
+            PropertyType::Float => "float",
+            PropertyType::Bool => "bool",
+            PropertyType::List => "list",
+            PropertyType::Map => "map",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDefinition {
+    pub name: String,
+    pub ty: PropertyType,
+    pub required: bool,
+}
+
+impl PropertyDefinition {
+    pub fn new(name: impl Into<String>, ty: PropertyType, required: bool) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            required,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LabelSchema {
+    properties: HashMap<Symbol, PropertyDefinition>,
+}
+
+impl LabelSchema {
+    pub fn properties(&self) -> impl Iterator<Item = &PropertyDefinition> {
+        self.properties.values()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EdgeSchema {
+    from: Symbol,
+    to: Symbol,
+}
+
+impl EdgeSchema {
+    pub fn from(&self) -> Symbol {
+        self.from
+    }
+
+    pub fn to(&self) -> Symbol {
+        self.to
+    }
+}
+
+/// Schemas of every defined node label and edge type.
+///
+/// Labels and edge types without a definition are free-form.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    labels: HashMap<Symbol, LabelSchema>,
+    edges: HashMap<Symbol, EdgeSchema>,
+}
+
+impl Catalog {
+    pub fn label(&self, label: Symbol) -> Option<&LabelSchema> {
+        self.labels.get(&label)
+    }
+
+    pub fn edge(&self, label: Symbol) -> Option<&EdgeSchema> {
+        self.edges.get(&label)
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = (Symbol, &LabelSchema)> {
+        self.labels.iter().map(|(&label, schema)| (label, schema))
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Symbol, &EdgeSchema)> {
+        self.edges.iter().map(|(&label, schema)| (label, schema))
+    }
+
+    pub(crate) fn define_label(
+        &mut self,
+        symbols: &mut SymbolTable,
+        label: &str,
+        properties: Vec<PropertyDefinition>,
+    ) -> Symbol {
+        let schema = LabelSchema {
+            properties: properties
+                .into_iter()
+                .map(|definition| (symbols.intern(&definition.name), definition))
+                .collect(),
+        };
+        let label = symbols.intern(label);
+        self.labels.insert(label, schema);
+
+        label
+    }
+
+    pub(crate) fn define_edge(
+        &mut self,
+        symbols: &mut SymbolTable,
+        label: &str,
+        from: &str,
+        to: &str,
+    ) -> Symbol {
+        let schema = EdgeSchema {
+            from: symbols.intern(from),
+            to: symbols.intern(to),
+        };
+        let label = symbols.intern(label);
+        self.edges.insert(label, schema);
+
+        label
+    }
+
+    /// Checks a node's properties against the schemas of all of its defined labels.
+    pub(crate) fn validate_node(
+        &self,
+        symbols: &SymbolTable,
+        labels: &[Symbol],
+        properties: &HashMap<Symbol, Value>,
+    ) -> Result<(), SchemaViolation> {
+        let schemas = labels
+            .iter()
+            .filter_map(|&label| Some((label, self.labels.get(&label)?)))
+            .collect::<Vec<_>>();
+
+        if schemas.is_empty() {
+            return Ok(());
+        }
+
+        for (label, schema) in &schemas {
+            for (key, definition) in &schema.properties {
+                match properties.get(key) {
+                    Some(value) if !definition.ty.accepts(value) => {
+                        return Err(SchemaViolation::TypeMismatch {
+                            label: symbols.resolve(*label).to_string(),
+                            property: definition.name.clone(),
+                            expected: definition.ty,
+                            found: value.type_name(),
+                        });
+                    }
+                    None if definition.required => {
+                        return Err(SchemaViolation::MissingProperty {
+                            label: symbols.resolve(*label).to_string(),
+                            property: definition.name.clone(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for key in properties.keys() {
+            let declared = schemas
+                .iter()
+                .any(|(_, schema)| schema.properties.contains_key(key));
+
+            if !declared && symbols.resolve(*key) != RECORD_ID {
+                return Err(SchemaViolation::UndeclaredProperty {
+                    labels: schemas
+                        .iter()
+                        .map(|(label, _)| symbols.resolve(*label).to_string())
+                        .collect(),
+                    property: symbols.resolve(*key).to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the ends of an edge carry the labels its definition requires.
+    pub(crate) fn validate_edge(
+        &self,
+        symbols: &SymbolTable,
+        label: Symbol,
+        from: &[Symbol],
+        to: &[Symbol],
+    ) -> Result<(), SchemaViolation> {
+        let Some(schema) = self.edges.get(&label) else {
+            return Ok(());
+        };
+
+        if !from.contains(&schema.from) {
+            return Err(SchemaViolation::InvalidSource {
+                edge: symbols.resolve(label).to_string(),
+                expected: symbols.resolve(schema.from).to_string(),
+            });
+        }
+
+        if !to.contains(&schema.to) {
+            return Err(SchemaViolation::InvalidTarget {
+                edge: symbols.resolve(label).to_string(),
+                expected: symbols.resolve(schema.to).to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaViolation {
+    MissingProperty {
+        label: String,
+        property: String,
+    },
+    TypeMismatch {
+        label: String,
+        property: String,
+        expected: PropertyType,
+        found: &'static str,
+    },
+    UndeclaredProperty {
+        labels: Vec<String>,
+        property: String,
+    },
+    InvalidSource {
+        edge: String,
+        expected: String,
+    },
+    InvalidTarget {
+        edge: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaViolation::MissingProperty { label, property } => {
+                write!(f, "label `{label}` requires property `{property}`")
+            }
+            SchemaViolation::TypeMismatch {
+                label,
+                property,
+                expected,
+                found,
+            } => write!(
+                f,
+                "property `{property}` of label `{label}` must be {expected}, got {found}"
+            ),
+            SchemaViolation::UndeclaredProperty { labels, property } => write!(
+                f,
+                "property `{property}` is not declared by label `{}`",
+                labels.join("`, `")
+            ),
+            SchemaViolation::InvalidSource { edge, expected } => {
+                write!(
+                    f,
+                    "edge `{edge}` must start at a node labelled `{expected}`"
+                )
+            }
+            SchemaViolation::InvalidTarget { edge, expected } => {
+                write!(f, "edge `{edge}` must end at a node labelled `{expected}`")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Graph, GraphError};
+
+    fn book_schema() -> Graph {
+        let mut graph = Graph::new();
+        graph
+            .define_label(
+                "book",
+                vec![
+                    PropertyDefinition::new("title", PropertyType::String, true),
+                    PropertyDefinition::new("rating", PropertyType::Float, false),
+                ],
+            )
+            .unwrap();
+        graph
+    }
+
+    fn book(properties: &[(&str, Value)]) -> (Vec<String>, HashMap<String, Value>) {
+        let properties = properties
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect();
+        (vec!["book".to_string()], properties)
+    }
+
+    fn add(
+        graph: &mut Graph,
+        (labels, properties): (Vec<String>, HashMap<String, Value>),
+    ) -> Result<usize, GraphError> {
+        graph.add_node(labels, properties)
+    }
+
+    #[test]
+    fn required_properties_must_be_present() {
+        let mut graph = book_schema();
+
+        assert_eq!(
+            add(&mut graph, book(&[("rating", Value::Float(4.5))])),
+            Err(GraphError::Schema(
+                None,
+                SchemaViolation::MissingProperty {
+                    label: "book".to_string(),
+                    property: "title".to_string(),
+                }
+            ))
+        );
+        let id = add(
+            &mut graph,
+            book(&[("title", Value::String("Dune".to_string()))]),
+        )
+        .unwrap();
+        assert!(matches!(
+            graph.remove_property(id, "title"),
+            Err(GraphError::Schema(Some(node), SchemaViolation::MissingProperty { .. })) if node == id
+        ));
+        assert_eq!(graph.node_count(), 1);
+    }
+
+    #[test]
+    fn properties_must_have_their_declared_type() {
+        let mut graph = book_schema();
+
+        assert_eq!(
+            add(&mut graph, book(&[("title", Value::Int(1))])),
+            Err(GraphError::Schema(
+                None,
+                SchemaViolation::TypeMismatch {
+                    label: "book".to_string(),
+                    property: "title".to_string(),
+                    expected: PropertyType::String,
+                    found: "int",
+                }
+            ))
+        );
+        // ints widen to floats, the record id is always allowed and nothing else is
+        let title = ("title", Value::String("Dune".to_string()));
+        assert!(
+            add(
+                &mut graph,
+                book(&[title.clone(), ("rating", Value::Int(4))])
+            )
+            .is_ok()
+        );
+        assert!(
+            add(
+                &mut graph,
+                book(&[
+                    title.clone(),
+                    (RECORD_ID, Value::String("dune".to_string()))
+                ])
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            add(&mut graph, book(&[title, ("pages", Value::Int(412))])),
+            Err(GraphError::Schema(
+                None,
+                SchemaViolation::UndeclaredProperty {
+                    labels: vec!["book".to_string()],
+                    property: "pages".to_string(),
+                }
+            ))
+        );
+        // labels without a schema stay free-form
+        assert!(
+            graph
+                .add_node(
+                    vec!["note".to_string()],
+                    HashMap::from([("pages".to_string(), Value::Bool(true))])
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn edges_must_connect_the_declared_labels() {
+        let mut graph = Graph::new();
+        graph.define_edge("wrote", "author", "book").unwrap();
+        let author = graph
+            .add_node(vec!["author".to_string()], HashMap::new())
+            .unwrap();
+        let book = graph
+            .add_node(vec!["book".to_string()], HashMap::new())
+            .unwrap();
+
+        assert_eq!(
+            graph.add_edge(book, author, "wrote".to_string()),
+            Err(GraphError::Schema(
+                Some(book),
+                SchemaViolation::InvalidSource {
+                    edge: "wrote".to_string(),
+                    expected: "author".to_string(),
+                }
+            ))
+        );
+        assert_eq!(
+            graph.add_edge(author, author, "wrote".to_string()),
+            Err(GraphError::Schema(
+                Some(author),
+                SchemaViolation::InvalidTarget {
+                    edge: "wrote".to_string(),
+                    expected: "book".to_string(),
+                }
+            ))
+        );
+        assert_eq!(graph.add_edge(author, book, "wrote".to_string()), Ok(()));
+        assert_eq!(graph.add_edge(book, author, "cites".to_string()), Ok(()));
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn schemas_are_refused_over_data_that_violates_them() {
+        let mut graph = Graph::new();
+        let untitled = graph
+            .add_node(vec!["book".to_string()], HashMap::new())
+            .unwrap();
+        let author = graph
+            .add_node(vec!["author".to_string()], HashMap::new())
+            .unwrap();
+        graph
+            .add_edge(untitled, author, "wrote".to_string())
+            .unwrap();
+
+        let title = PropertyDefinition::new("title", PropertyType::String, true);
+        assert!(matches!(
+            graph.define_label("book", vec![title.clone()]),
+            Err(GraphError::Schema(Some(id), SchemaViolation::MissingProperty { .. })) if id == untitled
+        ));
+        assert!(matches!(
+            graph.define_edge("wrote", "author", "book"),
+            Err(GraphError::Schema(Some(id), SchemaViolation::InvalidSource { .. })) if id == untitled
+        ));
+        assert_eq!(graph.catalog().labels().count(), 0);
+        assert_eq!(graph.catalog().edges().count(), 0);
+
+        graph
+            .set_property(untitled, "title", Value::String("Dune".to_string()))
+            .unwrap();
+        assert_eq!(graph.define_label("book", vec![title]), Ok(()));
+        assert!(
+            graph
+                .catalog()
+                .label(graph.symbols().get("book").unwrap())
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::graph::csr::CsrSnapshot;
+use crate::graph::error::GraphError;
+use crate::graph::graph::{Graph, Node};
+use crate::graph::mvcc::{MvccGraph, Snapshot};
+use crate::graph::topology::Topology;
+use crate::graph::transaction::Transaction;
+use crate::graph::value::Value;
+
+/// Cloneable handle sharing one [`Graph`] between threads.
+///
+/// Writes go to the graph one at a time, so its schemas and constraints hold as usual, and each
+/// is then published to an [`MvccGraph`] mirroring it. Reads go through snapshots of the mirror:
+/// they never wait for writers and never see half of a write. A [`view`](SharedGraph::view)
+/// keeps seeing the state it was opened at for as long as it is held.
+///
+/// Every write method stands on its own, so read-modify-write sequences that must not
+/// interleave with other writers go through [`update_property`](SharedGraph::update_property),
+/// [`write`](SharedGraph::write) or [`transaction`](SharedGraph::transaction).
+#[derive(Clone)]
+pub struct SharedGraph {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    graph: RwLock<Graph>,
+    versions: MvccGraph,
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedGraph>();
+};
+
+impl SharedGraph {
+    pub fn new(mut graph: Graph) -> Self {
+        graph.track_changes();
+        let versions = MvccGraph::from(&graph);
+
+        Self {
+            shared: Arc::new(Shared {
+                graph: RwLock::new(graph),
+                versions,
+            }),
+        }
+    }
+
+    /// Consistent read-only view of the latest published state.
+    pub fn view(&self) -> Snapshot<'_> {
+        self.shared.versions.snapshot()
+    }
+
+    /// Runs `f` with shared access to the graph itself, waiting for writers; reads that a
+    /// [`view`](SharedGraph::view) offers do not.
+    pub fn read<T>(&self, f: impl FnOnce(&Graph) -> T) -> T {
+        f(&self.lock_read())
+    }
+
+    /// Runs `f` with exclusive access to the graph and publishes what it wrote.
+    pub fn write<T>(&self, f: impl FnOnce(&mut Graph) -> T) -> T {
+        let mut graph = self.lock_write();
+        let result = f(&mut graph);
+        self.publish(&mut graph);
+
+        result
+    }
+
+    /// Runs `f` in a [`Transaction`], committing if it returns `Ok` and rolling back otherwise.
+    pub fn transaction<T, E>(
+        &self,
+        f: impl FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.write(|graph| {
+            let mut transaction = graph.begin();
+            let result = f(&mut transaction);
+
+            match result {
+                Ok(_) => transaction.commit(),
+                Err(_) => transaction.rollback(),
+            }
+
+            result
+        })
+    }
+
+    pub fn get_node(&self, id: usize) -> Option<Node> {
+        Some(self.view().get_node(id)?.as_ref().clone())
+    }
+
+    pub fn get_neighbors(&self, id: usize) -> Option<Vec<usize>> {
+        self.view().get_neighbors(id)
+    }
+
+    /// Value of the property `key` of node `id`.
+    pub fn property(&self, id: usize, key: &str) -> Option<Value> {
+        self.view().property(id, key)
+    }
+
+    pub fn dfs(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        self.view().dfs(from, to)
+    }
+
+    pub fn bfs(&self, from: usize) -> Vec<usize> {
+        self.view().bfs(from)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.view().node_count()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.view().edge_count()
+    }
+
+    /// Freezes the current state into a [`CsrSnapshot`] for whole-graph analytics.
+    pub fn snapshot(&self) -> CsrSnapshot {
+        self.lock_read().snapshot()
+    }
+
+    pub fn add_node(
+        &self,
+        labels: Vec<String>,
+        properties: HashMap<String, Value>,
+    ) -> Result<usize, GraphError> {
+        self.write(|graph| graph.add_node(labels, properties))
+    }
+
+    pub fn add_edge(&self, from: usize, to: usize, label: String) -> Result<(), GraphError> {
+        self.write(|graph| graph.add_edge(from, to, label))
+    }
+
+    /// Sets the property `key` of node `id`, returning the value it replaced.
+    pub fn set_property(
+        &self,
+        id: usize,
+        key: &str,
+        value: Value,
+    ) -> Result<Option<Value>, GraphError> {
+        self.write(|graph| graph.set_property(id, key, value))
+    }
+
+    /// Replaces the property `key` of node `id` with `f` applied to its current value, without
+    /// any other write in between, and returns the value it replaced.
+    pub fn update_property(
+        &self,
+        id: usize,
+        key: &str,
+        f: impl FnOnce(Option<&Value>) -> Value,
+    ) -> Result<Option<Value>, GraphError> {
+        self.write(|graph| {
+            let value = f(graph.property(id, key));
+            graph.set_property(id, key, value)
+        })
+    }
+
+    fn publish(&self, graph: &mut Graph) {
+        let changes = graph.take_changes();
+        self.shared.versions.publish(graph, changes);
+    }
+
+    fn lock_read(&self) -> RwLockReadGuard<'_, Graph> {
+        self.shared.graph.read().expect("graph lock poisoned")
+    }
+
+    fn lock_write(&self) -> RwLockWriteGuard<'_, Graph> {
+        self.shared.graph.write().expect("graph lock poisoned")
+    }
+}
+
+impl Default for SharedGraph {
+    fn default() -> Self {
+        Self::new(Graph::new())
+    }
+}
+
+impl From<Graph> for SharedGraph {
+    fn from(graph: Graph) -> Self {
+        Self::new(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+    use crate::graph::Constraint;
+
+    const THREADS: usize = 8;
+    const WRITES: usize = 100;
+
+    fn counter(graph: &SharedGraph) -> usize {
+        graph
+            .add_node(
+                vec!["counter".to_string()],
+                HashMap::from([("n".to_string(), Value::Int(0))]),
+            )
+            .unwrap()
+    }
+
+    fn increment(value: Option<&Value>) -> Value {
+        match value {
+            Some(Value::Int(n)) => Value::Int(n + 1),
+            other => panic!("counter holds {other:?}"),
+        }
+    }
+
+    fn total() -> Option<Value> {
+        Some(Value::Int((THREADS * WRITES) as i64))
+    }
+
+    #[test]
+    fn concurrent_add_node_hands_out_every_id_once() {
+        let graph = SharedGraph::default();
+
+        let ids = thread::scope(|scope| {
+            let handles = (0..THREADS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..WRITES)
+                            .map(|_| graph.add_node(Vec::new(), HashMap::new()).unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<HashSet<_>>()
+        });
+
+        assert_eq!(ids, (0..THREADS * WRITES).collect());
+        assert_eq!(graph.node_count(), THREADS * WRITES);
+        assert_eq!(graph.read(Graph::node_count), THREADS * WRITES);
+    }
+
+    #[test]
+    fn update_property_loses_no_increments() {
+        let graph = SharedGraph::default();
+        let id = counter(&graph);
+
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..WRITES {
+                        graph.update_property(id, "n", increment).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(graph.property(id, "n"), total());
+    }
+
+    #[test]
+    fn write_loses_no_increments() {
+        let graph = SharedGraph::default();
+        let id = counter(&graph);
+
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..WRITES {
+                        graph.write(|graph| {
+                            let value = increment(graph.property(id, "n"));
+                            graph.set_property(id, "n", value).unwrap();
+                        });
+                    }
+                });
+            }
+        });
+
+        assert_eq!(graph.property(id, "n"), total());
+    }
+
+    #[test]
+    fn transactions_apply_whole_or_not_at_all() {
+        let graph = SharedGraph::default();
+        let id = counter(&graph);
+        graph
+            .write(|graph| {
+                graph.create_constraint(Constraint::Unique {
+                    label: "entry".to_string(),
+                    property: "key".to_string(),
+                })
+            })
+            .unwrap();
+
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let graph = &graph;
+                scope.spawn(move || {
+                    for write in 0..WRITES {
+                        // every other transaction fails on a duplicate key after writing
+                        let key = match write % 2 {
+                            0 => format!("{thread}-{write}"),
+                            _ => "taken".to_string(),
+                        };
+                        let _ = graph.transaction(|transaction| {
+                            let value = increment(transaction.property(id, "n"));
+                            transaction.set_property(id, "n", value)?;
+                            let entry = transaction.add_node(
+                                vec!["entry".to_string()],
+                                HashMap::from([("key".to_string(), Value::String(key))]),
+                            )?;
+                            transaction.add_edge(id, entry, "holds".to_string())
+                        });
+                    }
+                });
+            }
+        });
+
+        // the first `taken` entry commits, every later one rolls back entirely
+        let committed = THREADS * WRITES / 2 + 1;
+        assert_eq!(graph.property(id, "n"), Some(Value::Int(committed as i64)));
+        assert_eq!(graph.node_count(), committed + 1);
+        assert_eq!(graph.edge_count(), committed);
+        assert_eq!(
+            graph.get_neighbors(id).map(|ids| ids.len()),
+            Some(committed)
+        );
+        assert_eq!(graph.read(Graph::edge_count), committed);
+    }
+
+    #[test]
+    fn views_never_see_half_of_a_write() {
+        let graph = SharedGraph::default();
+        let (a, b) = (counter(&graph), counter(&graph));
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..THREADS * WRITES {
+                    graph
+                        .transaction(|transaction| {
+                            let value = increment(transaction.property(a, "n"));
+                            transaction.set_property(a, "n", value.clone())?;
+                            transaction.set_property(b, "n", value)
+                        })
+                        .unwrap();
+                }
+            });
+
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..WRITES {
+                        let view = graph.view();
+                        let n = view.property(a, "n");
+                        assert_eq!(view.property(b, "n"), n);
+                        thread::yield_now();
+                        assert_eq!(view.property(a, "n"), n);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(graph.property(a, "n"), total());
+        assert_eq!(graph.property(b, "n"), total());
+    }
+
+    #[test]
+    fn reads_do_not_wait_for_writers() {
+        let graph = SharedGraph::default();
+        let id = counter(&graph);
+        let (entered, proceed) = (Barrier::new(2), Barrier::new(2));
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                graph.write(|graph| {
+                    graph.set_property(id, "n", Value::Int(5)).unwrap();
+                    entered.wait();
+                    proceed.wait();
+                })
+            });
+
+            // the writer holds the graph, yet reads go ahead and see the state before its write
+            entered.wait();
+            assert_eq!(graph.property(id, "n"), Some(Value::Int(0)));
+            assert_eq!(graph.bfs(id), [id]);
+            proceed.wait();
+        });
+
+        assert_eq!(graph.property(id, "n"), Some(Value::Int(5)));
+    }
+}
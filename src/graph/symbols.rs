@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
+/// Compact id of an interned label, edge type or property key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Interns strings so every distinct label, edge type and property key is stored only once.
+///
+/// Symbols are handed out in insertion order and are never freed, so a [`Symbol`] stays valid
+/// for the lifetime of the table it came from and of every clone of it.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    ids: HashMap<Arc<str>, Symbol>,
+    names: Vec<Arc<str>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.ids.get(name) {
+            return symbol;
+        }
+
+        let symbol = Symbol(
+            u32::try_from(self.names.len()).expect("symbol table exceeded u32::MAX entries"),
+        );
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(Arc::clone(&name));
+        self.ids.insert(name, symbol);
+
+        symbol
+    }
+
+    /// Symbol of an already interned string, without interning it.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (Symbol(index as u32), name.as_ref()))
+    }
+
+    /// Approximate number of heap bytes owned by the table.
+    pub fn heap_size(&self) -> usize {
+        // Every `Arc<str>` allocation carries its strong and weak counts in front of the bytes.
+        let strings = self
+            .names
+            .iter()
+            .map(|name| name.len() + 2 * size_of::<usize>())
+            .sum::<usize>();
+        let names = self.names.capacity() * size_of::<Arc<str>>();
+        let ids = self.ids.capacity() * (size_of::<Arc<str>>() + size_of::<Symbol>());
+
+        strings + names + ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::graph::{Graph, Value};
+
+    /// Counts the bytes allocated by each thread, so tests running alongside do not disturb it.
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // the count is gone while the thread is torn down, when there is nothing to measure
+            let _ = ALLOCATED
+                .try_with(|allocated| allocated.set(allocated.get() + layout.size() as isize));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let _ = ALLOCATED
+                .try_with(|allocated| allocated.set(allocated.get() - layout.size() as isize));
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: Counting = Counting;
+
+    /// Heap bytes `build` leaves allocated in what it returns.
+    fn measure<T>(build: impl FnOnce() -> T) -> isize {
+        let before = ALLOCATED.with(Cell::get);
+        let value = build();
+        let used = ALLOCATED.with(Cell::get) - before;
+        drop(value);
+        used
+    }
+
+    #[test]
+    fn interning_hands_out_one_symbol_per_string() {
+        let mut symbols = SymbolTable::new();
+        let book = symbols.intern("book");
+        let title = symbols.intern("title");
+
+        assert_eq!(symbols.intern("book"), book);
+        assert_ne!(book, title);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(
+            symbols.iter().collect::<Vec<_>>(),
+            [(book, "book"), (title, "title")]
+        );
+    }
+
+    #[test]
+    fn get_finds_only_interned_strings() {
+        let mut symbols = SymbolTable::new();
+        let book = symbols.intern("book");
+
+        assert_eq!(symbols.get("book"), Some(book));
+        assert_eq!(symbols.get("Book"), None);
+        assert_eq!(symbols.get(""), None);
+        assert_eq!(symbols.len(), 1);
+    }
+
+    #[test]
+    fn symbols_resolve_in_clones_of_the_table() {
+        let mut symbols = SymbolTable::new();
+        let book = symbols.intern("book");
+        let mut clone = symbols.clone();
+        let author = clone.intern("author");
+
+        assert_eq!(symbols.resolve(book), "book");
+        assert_eq!(clone.resolve(book), "book");
+        assert_eq!(clone.resolve(author), "author");
+        assert!(symbols.get("author").is_none());
+    }
+
+    #[test]
+    fn interned_names_take_less_memory_than_inline_strings() {
+        const NODES: usize = 10_000;
+
+        /// Node and edge layout from before interning, every name owned by its node or edge.
+        #[allow(dead_code)]
+        struct InlineNode {
+            id: usize,
+            labels: Vec<String>,
+            properties: HashMap<String, Value>,
+        }
+
+        #[allow(dead_code)]
+        struct InlineEdge {
+            from: usize,
+            to: usize,
+            label: String,
+        }
+
+        let properties = |id: usize| {
+            HashMap::from([
+                ("title".to_string(), Value::String(format!("Book {id}"))),
+                ("pages".to_string(), Value::Int(200)),
+            ])
+        };
+
+        let inline = measure(|| {
+            let mut nodes = HashMap::new();
+            let mut edges: HashMap<usize, Vec<InlineEdge>> = HashMap::new();
+            for id in 0..NODES {
+                let labels = vec!["book".to_string()];
+                let properties = properties(id);
+                nodes.insert(
+                    id,
+                    InlineNode {
+                        id,
+                        labels,
+                        properties,
+                    },
+                );
+                if id > 0 {
+                    let label = "sequel".to_string();
+                    edges.entry(id - 1).or_default().push(InlineEdge {
+                        from: id - 1,
+                        to: id,
+                        label,
+                    });
+                }
+            }
+            (nodes, edges)
+        });
+        let interned = measure(|| {
+            let mut graph = Graph::new();
+            for id in 0..NODES {
+                graph
+                    .add_node(vec!["book".to_string()], properties(id))
+                    .unwrap();
+                if id > 0 {
+                    graph.add_edge(id - 1, id, "sequel".to_string()).unwrap();
+                }
+            }
+            graph
+        });
+
+        assert!(interned > 0);
+        assert!(
+            interned < inline,
+            "interned names take {interned} bytes, inline strings {inline}"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::graph::csr::CsrSnapshot;
+
+/// Read-only view of the shape of a graph.
+///
+/// Implemented by the live [`Graph`](crate::graph::Graph) and by
+/// [`CsrSnapshot`](crate::graph::CsrSnapshot), so traversals and analytics can run against
+/// either representation.
+pub trait Topology {
+    fn node_count(&self) -> usize;
+
+    fn contains(&self, id: usize) -> bool;
+
+    fn node_ids(&self) -> impl Iterator<Item = usize> + '_;
+
+    fn neighbors(&self, id: usize) -> impl Iterator<Item = usize> + '_;
+
+    fn out_degree(&self, id: usize) -> usize {
+        self.neighbors(id).count()
+    }
+
+    /// Dense representation of this graph, if it already is one.
+    ///
+    /// Analytics use this to switch to index-based implementations.
+    fn as_csr(&self) -> Option<&CsrSnapshot> {
+        None
+    }
+
+    /// Depth-first search for a path from `from` to `to`, both ends included.
+    fn dfs(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        if !self.contains(from) {
+            return None;
+        }
+
+        let mut visited = HashSet::from([from]);
+        let mut path = vec![from];
+        let mut stack = vec![self.neighbors(from)];
+
+        while let Some(neighbors) = stack.last_mut() {
+            if *path.last()? == to {
+                return Some(path);
+            }
+
+            match neighbors.find(|n| !visited.contains(n)) {
+                Some(next) => {
+                    visited.insert(next);
+                    path.push(next);
+                    stack.push(self.neighbors(next));
+                }
+                None => {
+                    stack.pop();
+                    path.pop();
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Breadth-first order of every node reachable from `from`, starting with `from` itself.
+    fn bfs(&self, from: usize) -> Vec<usize> {
+        if !self.contains(from) {
+            return Vec::new();
+        }
+
+        let mut visited = HashSet::from([from]);
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            order.push(current);
+            for next in self.neighbors(current) {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        order
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+
+use crate::graph::constraint::Constraint;
+use crate::graph::error::GraphError;
+use crate::graph::graph::Graph;
+use crate::graph::schema::PropertyDefinition;
+use crate::graph::value::Value;
+
+/// Handle grouping writes to a [`Graph`] so they either all apply or none do.
+///
+/// Writes are staged on a copy of the graph taken when the transaction began. Reads through the
+/// handle see them; the graph itself does not change until [`commit`](Transaction::commit)
+/// replaces it with the copy. The handle borrows the graph mutably, so nobody writes to it in the
+/// meantime. Dropping the handle without committing discards the staged writes, like
+/// [`rollback`](Transaction::rollback).
+pub struct Transaction<'g> {
+    graph: &'g mut Graph,
+    staged: Graph,
+}
+
+impl<'g> Transaction<'g> {
+    pub(crate) fn new(graph: &'g mut Graph) -> Self {
+        let staged = graph.clone();
+
+        Self { graph, staged }
+    }
+
+    pub fn add_node(
+        &mut self,
+        labels: Vec<String>,
+        properties: HashMap<String, Value>,
+    ) -> Result<usize, GraphError> {
+        self.staged.add_node(labels, properties)
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, label: String) -> Result<(), GraphError> {
+        self.staged.add_edge(from, to, label)
+    }
+
+    pub fn set_property(
+        &mut self,
+        id: usize,
+        key: &str,
+        value: Value,
+    ) -> Result<Option<Value>, GraphError> {
+        self.staged.set_property(id, key, value)
+    }
+
+    pub fn remove_property(&mut self, id: usize, key: &str) -> Result<Option<Value>, GraphError> {
+        self.staged.remove_property(id, key)
+    }
+
+    pub fn define_label(
+        &mut self,
+        label: &str,
+        properties: Vec<PropertyDefinition>,
+    ) -> Result<(), GraphError> {
+        self.staged.define_label(label, properties)
+    }
+
+    pub fn define_edge(&mut self, label: &str, from: &str, to: &str) -> Result<(), GraphError> {
+        self.staged.define_edge(label, from, to)
+    }
+
+    pub fn create_constraint(&mut self, constraint: Constraint) -> Result<(), GraphError> {
+        self.staged.create_constraint(constraint)
+    }
+
+    /// Replaces the graph with the staged copy.
+    pub fn commit(self) {
+        *self.graph = self.staged;
+    }
+
+    /// Discards the staged writes.
+    pub fn rollback(self) {}
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Graph;
+
+    fn deref(&self) -> &Self::Target {
+        &self.staged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{PropertyType, Topology};
+
+    /// Everything a transaction may change, in a form that compares regardless of hash order.
+    fn state(graph: &Graph) -> String {
+        let symbols = graph.symbols();
+        let mut nodes = graph
+            .nodes()
+            .map(|node| {
+                let mut properties = node
+                    .properties()
+                    .iter()
+                    .map(|(&key, value)| format!("{}={value}", symbols.resolve(key)))
+                    .collect::<Vec<_>>();
+                properties.sort();
+                let labels = node.labels().iter().map(|&label| symbols.resolve(label));
+                (node.id(), labels.collect::<Vec<_>>(), properties)
+            })
+            .collect::<Vec<_>>();
+        nodes.sort();
+        let mut edges = graph
+            .edges()
+            .map(|edge| (edge.from(), edge.to(), symbols.resolve(edge.label())))
+            .collect::<Vec<_>>();
+        edges.sort();
+        let mut labels = graph
+            .catalog()
+            .labels()
+            .map(|(label, schema)| (symbols.resolve(label), schema.properties().count()))
+            .collect::<Vec<_>>();
+        labels.sort();
+        let edge_schemas = graph.catalog().edges().count();
+        let constraints = graph.constraints().collect::<Vec<_>>();
+
+        format!(
+            "{nodes:?} {edges:?} {labels:?} {edge_schemas} {constraints:?} {}",
+            graph.id_bound()
+        )
+    }
+
+    fn populated() -> Graph {
+        let mut graph = Graph::new();
+        let title = HashMap::from([("title".to_string(), Value::String("Dune".to_string()))]);
+        let book = graph.add_node(vec!["book".to_string()], title).unwrap();
+        let author = graph
+            .add_node(vec!["author".to_string()], HashMap::new())
+            .unwrap();
+        graph.add_edge(author, book, "wrote".to_string()).unwrap();
+        graph
+    }
+
+    /// Writes touching every part of the graph.
+    fn write_everything(transaction: &mut Transaction<'_>) {
+        let node = transaction
+            .add_node(vec!["book".to_string()], HashMap::new())
+            .unwrap();
+        transaction.add_edge(1, node, "wrote".to_string()).unwrap();
+        transaction
+            .set_property(0, "title", Value::String("Children of Dune".to_string()))
+            .unwrap();
+        transaction.set_property(1, "name", Value::Int(1)).unwrap();
+        transaction.remove_property(1, "name").unwrap();
+        transaction
+            .define_label(
+                "book",
+                vec![PropertyDefinition::new(
+                    "title",
+                    PropertyType::String,
+                    false,
+                )],
+            )
+            .unwrap();
+        transaction.define_edge("wrote", "author", "book").unwrap();
+        transaction
+            .create_constraint(Constraint::Unique {
+                label: "book".to_string(),
+                property: "title".to_string(),
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn rollback_leaves_the_graph_untouched() {
+        let mut graph = populated();
+        let before = state(&graph);
+
+        let mut transaction = graph.begin();
+        write_everything(&mut transaction);
+        // the transaction reads its own writes
+        assert_eq!(transaction.node_count(), 3);
+        assert_eq!(transaction.outgoing(1).len(), 2);
+        assert_ne!(state(&transaction), before);
+        transaction.rollback();
+
+        assert_eq!(state(&graph), before);
+        // ids handed out by the transaction are handed out again
+        let id = graph.add_node(Vec::new(), HashMap::new()).unwrap();
+        assert_eq!(id, 2);
+    }
+
+    #[test]
+    fn dropping_a_transaction_rolls_it_back() {
+        let mut graph = populated();
+        let before = state(&graph);
+
+        write_everything(&mut graph.begin());
+
+        assert_eq!(state(&graph), before);
+        assert_eq!(graph.bfs(1), [1, 0]);
+    }
+
+    #[test]
+    fn commit_applies_every_write() {
+        let mut graph = populated();
+        let mut transaction = graph.begin();
+        write_everything(&mut transaction);
+        let staged = state(&transaction);
+        transaction.commit();
+
+        assert_eq!(state(&graph), staged);
+        assert_eq!(graph.id_bound(), 3);
+        assert_eq!(graph.constraints().count(), 1);
+    }
+
+    #[test]
+    fn failed_writes_leave_the_rest_of_the_transaction_intact() {
+        let mut graph = populated();
+        let mut transaction = graph.begin();
+        transaction.set_property(1, "name", Value::Int(1)).unwrap();
+
+        assert_eq!(
+            transaction.add_edge(1, 7, "wrote".to_string()),
+            Err(GraphError::NodeNotFound(7))
+        );
+        transaction.commit();
+
+        assert_eq!(graph.property(1, "name"), Some(&Value::Int(1)));
+        assert_eq!(graph.edge_count(), 1);
+    }
+}
@@ -1,32 +1,334 @@
-mod graph;
-mod mql;
-
-use mql::lexer::{Lexer, Token, Span};
-use mql::parser::Parser;
-
-fn main() {
-    // let create_statement = Lexer::new("create author:jk { name = \"J.K. Rowling\" };").collect::<Vec<(Token, Span)>>();
-    // let create_with_link_statement = Lexer::new(r#"
-    // create book:hp1 {
-    //     title = "Philosopher’s Stone",
-    //     pages = 223,
-    //     author -> author:jk
-    // };"#).collect::<Vec<(Token, Span)>>();
-    // let another_create_statement = Lexer::new("create book:hp2 { title = \"Chamber of Secrets\" };").collect::<Vec<(Token, Span)>>();
-    // let link_statement = Lexer::new("link author:jk -> book:hp2;").collect::<Vec<(Token, Span)>>();
-    // let select_statement = Lexer::new("select * from author where name = \"J.K. Rowling\";"); //.collect::<Vec<(Token, Span)>>();
-    // let select_with_id_statement = Lexer::new("select title from book:hp1;"); // .collect::<Vec<(Token, Span)>>();
-    let select_with_relation_statement = Lexer::new("select * from author->book where title like \"Harry Potter\";"); //.collect::<Vec<(Token, Span)>>();
-    //
-    // println!("{create_statement:#?}");
-    // println!("{create_with_link_statement:#?}");
-    // println!("{another_create_statement:#?}");
-    // println!("{link_statement:#?}");
-    // println!("{select_statement:#?}");
-    // println!("{select_with_id_statement:#?}");
-    // println!("{select_with_relation_statement:#?}");
-    let mut parser = Parser::new(select_with_relation_statement);
-    let ast = parser.parse();
-
-    println!("{:?}", ast);
+mod database;
+mod repl;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use minerva::mql::format::format;
+use minerva::mql::lexer::Lexer;
+use minerva::mql::parser::Parser;
+use minerva::mql::script::Script;
+
+use crate::database::Database;
+use crate::repl::Repl;
+
+const USAGE: &str = "\
+usage: minerva [--db <dir>]
+       minerva run <file.mql> [--db <dir>] [--continue-on-error]
+       minerva fmt <file.mql> [--check]
+
+Without a command, starts an interactive shell. `run` executes every statement in a file and
+stops at the first failure unless `--continue-on-error` is given, without which a file with
+syntax errors is not executed at all. With `--db`, the graph is stored in the given directory
+instead of only in memory. `fmt` rewrites a file in canonical form, or with `--check` only
+fails if it is not.";
+
+enum Command {
+    Help,
+    Shell,
+    Run {
+        file: PathBuf,
+        continue_on_error: bool,
+    },
+    Format {
+        file: PathBuf,
+        check: bool,
+    },
+}
+
+struct Arguments {
+    command: Command,
+    database: Option<PathBuf>,
+}
+
+/// Whether [`run_script`] keeps executing statements after one fails.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum OnError {
+    Stop,
+    Continue,
+}
+
+/// Statements of a [`run_script`] that executed, and those that failed to parse or to execute.
+pub(crate) struct Summary {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+fn main() -> ExitCode {
+    let arguments = match parse_arguments(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match &arguments.command {
+        Command::Help => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Command::Format { file, check } => return format_file(file, *check),
+        Command::Shell | Command::Run { .. } => {}
+    }
+
+    let database = match &arguments.database {
+        Some(directory) => Database::open(directory),
+        None => Ok(Database::in_memory()),
+    };
+    let mut database = match database {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match arguments.command {
+        Command::Help | Command::Format { .. } => {
+            unreachable!("handled before opening the database")
+        }
+        Command::Shell => match Repl::new(database).run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Run {
+            file,
+            continue_on_error,
+        } => {
+            let on_error = if continue_on_error {
+                OnError::Continue
+            } else {
+                OnError::Stop
+            };
+
+            run_file(&mut database, &file, on_error)
+        }
+    }
+}
+
+fn parse_arguments(mut arguments: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut command = Command::Shell;
+    let mut database = None;
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--db" => {
+                let directory = arguments.next().ok_or("`--db` needs a directory")?;
+                database = Some(PathBuf::from(directory));
+            }
+            "--continue-on-error" => match &mut command {
+                Command::Run {
+                    continue_on_error, ..
+                } => *continue_on_error = true,
+                _ => return Err("`--continue-on-error` only applies to `run`".into()),
+            },
+            "--check" => match &mut command {
+                Command::Format { check, .. } => *check = true,
+                _ => return Err("`--check` only applies to `fmt`".into()),
+            },
+            "-h" | "--help" => command = Command::Help,
+            "run" if matches!(command, Command::Shell) => {
+                let file = arguments.next().ok_or("`run` needs a file")?;
+                command = Command::Run {
+                    file: PathBuf::from(file),
+                    continue_on_error: false,
+                };
+            }
+            "fmt" if matches!(command, Command::Shell) => {
+                let file = arguments.next().ok_or("`fmt` needs a file")?;
+                command = Command::Format {
+                    file: PathBuf::from(file),
+                    check: false,
+                };
+            }
+            other => return Err(format!("unexpected argument `{other}`")),
+        }
+    }
+
+    Ok(Arguments { command, database })
+}
+
+/// Rewrites `file` in canonical form or, with `check`, only reports whether it already is.
+fn format_file(file: &Path, check: bool) -> ExitCode {
+    let source = match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read `{}`: {error}", file.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let name = file.display().to_string();
+    let script = Script::new(&source);
+    let formatted = match format(&script) {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            for error in errors {
+                eprint!("{}", error.diagnostic().render(script.source(), &name));
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if formatted == source {
+        return ExitCode::SUCCESS;
+    }
+    if check {
+        eprintln!("{name}: not formatted");
+        return ExitCode::FAILURE;
+    }
+    if let Err(error) = fs::write(file, formatted) {
+        eprintln!("error: cannot write `{}`: {error}", file.display());
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn run_file(database: &mut Database, file: &Path, on_error: OnError) -> ExitCode {
+    let source = match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read `{}`: {error}", file.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let name = file.display().to_string();
+    let summary = run_script(database, &Script::new(&source), Some(&name), on_error);
+    if database.in_transaction() {
+        eprintln!("warning: `{name}` leaves a transaction open, it was rolled back");
+    }
+
+    eprintln!(
+        "{name}: {} statements succeeded, {} failed",
+        summary.succeeded, summary.failed
+    );
+
+    if summary.failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Executes the statements of `script` in order, printing every outcome and failure.
+///
+/// Every statement that does not parse is reported before any is executed, and with
+/// [`OnError::Stop`] none is. Execution failures are prefixed with `name:line:column` of the
+/// failing statement when a name is given.
+pub(crate) fn run_script(
+    database: &mut Database,
+    script: &Script,
+    name: Option<&str>,
+    on_error: OnError,
+) -> Summary {
+    let (statements, errors) = Parser::new(Lexer::new(script.source())).parse_recovering();
+    for error in &errors {
+        eprint!(
+            "{}",
+            error
+                .diagnostic()
+                .render(script.source(), name.unwrap_or("<input>"))
+        );
+    }
+
+    let mut summary = Summary {
+        succeeded: 0,
+        failed: errors.len(),
+    };
+    if !errors.is_empty() && on_error == OnError::Stop {
+        return summary;
+    }
+
+    for (ast, span) in statements {
+        let statement = &script.source()[span.start()..span.end()];
+        match database.execute_parsed(ast, statement) {
+            Ok(outcome) => {
+                summary.succeeded += 1;
+                println!("{outcome}");
+            }
+            Err(error) => {
+                summary.failed += 1;
+                match name {
+                    Some(name) => {
+                        eprintln!("{name}:{}: error: {error}", script.location(span.start()))
+                    }
+                    None => eprintln!("error: {error}"),
+                }
+
+                if on_error == OnError::Stop {
+                    break;
+                }
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, on_error: OnError) -> (Summary, usize) {
+        let mut database = Database::in_memory();
+        let summary = run_script(
+            &mut database,
+            &Script::new(source),
+            Some("test.mql"),
+            on_error,
+        );
+
+        (summary, database.graph().node_count())
+    }
+
+    #[test]
+    fn syntax_errors_keep_a_script_from_running() {
+        let source = "create a:x { };\nselect from a;\ncreate a:y { };\nlink a:x -> ;";
+
+        let (summary, nodes) = run(source, OnError::Stop);
+        assert_eq!((summary.succeeded, summary.failed, nodes), (0, 2, 0));
+
+        let (summary, nodes) = run(source, OnError::Continue);
+        assert_eq!((summary.succeeded, summary.failed, nodes), (2, 2, 2));
+    }
+
+    #[test]
+    fn unclosed_comments_are_syntax_errors() {
+        for comment in ["/* fixtures for src/*.mql */", "/* to do"] {
+            let source =
+                format!("create a:x {{ }};\n{comment}\ncreate a:y {{ }};\ncreate a:z {{ }};");
+
+            let (summary, nodes) = run(&source, OnError::Stop);
+            assert_eq!(
+                (summary.succeeded, summary.failed, nodes),
+                (0, 1, 0),
+                "{comment}"
+            );
+
+            let (summary, nodes) = run(&source, OnError::Continue);
+            assert_eq!(
+                (summary.succeeded, summary.failed, nodes),
+                (1, 1, 1),
+                "{comment}"
+            );
+        }
+    }
+
+    #[test]
+    fn execution_failures_stop_the_script_unless_told_otherwise() {
+        let source = "create a:x { };\nlink a:x -> a:missing;\ncreate a:y { }";
+
+        let (summary, nodes) = run(source, OnError::Stop);
+        assert_eq!((summary.succeeded, summary.failed, nodes), (1, 1, 1));
+
+        let (summary, nodes) = run(source, OnError::Continue);
+        assert_eq!((summary.succeeded, summary.failed, nodes), (2, 1, 2));
+    }
 }
@@ -0,0 +1,186 @@
+use std::fmt;
+
+use crate::mql::lexer::Span;
+
+/// Line and column of a position in source text, both counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Location of the byte `offset` in `source`.
+    pub fn of(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Error pointing at a span of source text, rendered the way rustc renders its errors:
+///
+/// ```text
+/// error: expected `from`, found `where`
+///  --> seed.mql:1:13
+///   |
+/// 1 | select name where age > 3;
+///   |             ^^^^^ expected `from`
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    /// Note printed next to the underline, may be empty.
+    pub label: String,
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic against the `source` its span points into, naming that source
+    /// `origin` (usually a file name).
+    pub fn render(&self, source: &str, origin: &str) -> String {
+        let start = self.span.start().min(source.len());
+        let end = self.span.end().clamp(start, source.len());
+        let location = Location::of(source, start);
+
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |newline| start + newline);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // tabs are kept in the padding so the carets line up under them
+        let padding = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(source[start..end.min(line_end)].chars().count().max(1));
+
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        let mut rendered = format!("error: {}\n", self.message);
+        rendered += &format!("{gutter}--> {origin}:{location}\n");
+        rendered += &format!("{gutter} |\n");
+        rendered += &format!("{number} | {line}\n");
+        rendered += &format!("{gutter} | {padding}{carets}");
+        if !self.label.is_empty() {
+            rendered += &format!(" {}", self.label);
+        }
+        rendered.push('\n');
+
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, start: usize, end: usize, label: &str) -> String {
+        let diagnostic = Diagnostic {
+            message: "broken".to_string(),
+            span: Span::new(start, end),
+            label: label.to_string(),
+        };
+        diagnostic.render(source, "seed.mql")
+    }
+
+    #[test]
+    fn carets_underline_the_span() {
+        let diagnostic = Diagnostic {
+            message: "expected `from`, found `where`".to_string(),
+            span: Span::new(12, 17),
+            label: "expected `from`".to_string(),
+        };
+
+        assert_eq!(
+            diagnostic.render("select name where age > 3;", "seed.mql"),
+            "error: expected `from`, found `where`\n \
+             --> seed.mql:1:13\n  \
+             |\n\
+             1 | select name where age > 3;\n  \
+             |             ^^^^^ expected `from`\n"
+        );
+    }
+
+    #[test]
+    fn the_line_holding_the_span_is_shown() {
+        let source = "select *\r\nfrom a\r\nwhere;";
+
+        assert_eq!(
+            render(source, 18, 23, ""),
+            "error: broken\n --> seed.mql:3:1\n  |\n3 | where;\n  | ^^^^^\n"
+        );
+        assert_eq!(Location::of(source, 18), Location { line: 3, column: 1 });
+    }
+
+    #[test]
+    fn the_gutter_is_as_wide_as_the_line_number() {
+        let source = format!("{}select * frm a;", ";\n".repeat(9));
+
+        assert_eq!(
+            render(&source, 27, 30, "here"),
+            "error: broken\n  --> seed.mql:10:10\n   |\n10 | select * frm a;\n   |          ^^^ here\n"
+        );
+    }
+
+    #[test]
+    fn carets_stop_at_the_end_of_the_line() {
+        assert_eq!(
+            render("select * from\nbook;", 9, 18, ""),
+            "error: broken\n --> seed.mql:1:10\n  |\n1 | select * from\n  |          ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn tabs_are_kept_in_the_padding() {
+        assert_eq!(
+            render("\tselect\t* frm a;", 10, 13, ""),
+            "error: broken\n --> seed.mql:1:11\n  |\n1 | \tselect\t* frm a;\n  | \t      \t  ^^^\n"
+        );
+    }
+
+    #[test]
+    fn columns_and_carets_count_characters() {
+        let source = "create a:x { name = \"Zoë\" } oops";
+
+        assert_eq!(
+            render(source, 29, 33, ""),
+            "error: broken\n --> seed.mql:1:29\n  |\n1 | create a:x { name = \"Zoë\" } oops\n  \
+             |                             ^^^^\n"
+        );
+        assert_eq!(
+            render(source, 21, 25, ""),
+            "error: broken\n --> seed.mql:1:22\n  |\n1 | create a:x { name = \"Zoë\" } oops\n  \
+             |                      ^^^\n"
+        );
+    }
+
+    #[test]
+    fn spans_at_the_end_of_the_input_get_one_caret() {
+        assert_eq!(
+            render("select * from", 13, 13, "input ends here"),
+            "error: broken\n --> seed.mql:1:14\n  |\n1 | select * from\n  |              ^ input ends here\n"
+        );
+        assert_eq!(
+            render("select * from\n", 14, 14, ""),
+            "error: broken\n --> seed.mql:2:1\n  |\n2 | \n  | ^\n"
+        );
+        // spans past the end are clamped to it
+        assert_eq!(
+            render("select", 10, 12, ""),
+            "error: broken\n --> seed.mql:1:7\n  |\n1 | select\n  |       ^\n"
+        );
+    }
+}
@@ -0,0 +1,2602 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use regex::Regex;
+
+use crate::graph::{self, Graph, GraphError, IndexKey, Node, RECORD_ID};
+use crate::mql::like::LikePattern;
+use crate::mql::parser::{
+    AST, AggregateFunction, ArithmeticOperator, Direction, EntityDescription, Expression,
+    FilterExpression, Operator, PathExpression, Projection, Query, SortKey, Value,
+};
+use crate::mql::prepared::{Parameters, Prepared};
+use crate::mql::rows::Rows;
+
+/// What a successfully executed statement did.
+#[derive(Debug)]
+pub enum Outcome {
+    Selected(Rows),
+    Created(usize),
+    Linked,
+    Defined,
+    Began,
+    Committed,
+    RolledBack,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Selected(rows) => write!(f, "{rows}"),
+            Outcome::Created(id) => write!(f, "created node {id}"),
+            Outcome::Linked => write!(f, "linked"),
+            Outcome::Defined => write!(f, "defined"),
+            Outcome::Began => write!(f, "begin"),
+            Outcome::Committed => write!(f, "commit"),
+            Outcome::RolledBack => write!(f, "rollback"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExecutionError {
+    Graph(GraphError),
+    /// A statement referenced `label` where it needs a `label:id` record.
+    MissingRecordId(String),
+    RecordNotFound(String, String),
+    DuplicateRecord(String, String),
+    /// Lists cannot hold `null`, as stored values have no null.
+    NullInList,
+    Unsupported(&'static str),
+    TransactionAlreadyOpen,
+    NoTransaction,
+    /// A statement failed earlier in the open transaction, which can only be rolled back now.
+    TransactionAborted,
+    UnknownFunction(String),
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// An operator or function was applied to values of the listed types, which it does not take.
+    InvalidOperands(String, Vec<&'static str>),
+    DivisionByZero,
+    Overflow,
+    /// A query with aggregates or `group by` reads a property outside of both.
+    UngroupedProperty(String),
+    /// An aggregate appears where it has no group to aggregate, such as in `where`.
+    MisplacedAggregate(&'static str),
+    GroupedAll,
+    /// `step.property` where more than one step of the path has the label `step`.
+    AmbiguousStep(String),
+    /// Source and parse error of a regular expression.
+    InvalidPattern(String, String),
+    /// A statement uses the parameter `$name` but no value was given for it.
+    MissingParameter(String),
+    /// The value given for a parameter has a type its position does not take.
+    MistypedParameter {
+        name: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::Graph(error) => write!(f, "{error}"),
+            ExecutionError::MissingRecordId(label) => {
+                write!(f, "expected a record like `{label}:id`, got `{label}`")
+            }
+            ExecutionError::RecordNotFound(label, id) => {
+                write!(f, "record `{label}:{id}` does not exist")
+            }
+            ExecutionError::DuplicateRecord(label, id) => {
+                write!(f, "record `{label}:{id}` already exists")
+            }
+            ExecutionError::NullInList => write!(f, "lists cannot contain `null`"),
+            ExecutionError::Unsupported(statement) => {
+                write!(f, "`{statement}` statements cannot be executed yet")
+            }
+            ExecutionError::TransactionAlreadyOpen => {
+                write!(f, "a transaction is already open")
+            }
+            ExecutionError::NoTransaction => write!(f, "no transaction is open"),
+            ExecutionError::TransactionAborted => write!(
+                f,
+                "the transaction failed and was rolled back, end it with `rollback`"
+            ),
+            ExecutionError::UnknownFunction(function) => {
+                write!(f, "unknown function `{function}`")
+            }
+            ExecutionError::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{function}` takes {expected} argument{}, got {found}",
+                if *expected == 1 { "" } else { "s" }
+            ),
+            ExecutionError::InvalidOperands(operation, types) => {
+                write!(
+                    f,
+                    "`{operation}` cannot be applied to {}",
+                    types.join(" and ")
+                )
+            }
+            ExecutionError::DivisionByZero => write!(f, "division by zero"),
+            ExecutionError::Overflow => write!(f, "integer overflow"),
+            ExecutionError::UngroupedProperty(property) => write!(
+                f,
+                "`{property}` must appear in `group by` or be used in an aggregate"
+            ),
+            ExecutionError::MisplacedAggregate(place) => {
+                write!(f, "aggregates cannot be used in {place}")
+            }
+            ExecutionError::MissingParameter(name) => {
+                write!(f, "no value given for parameter `${name}`")
+            }
+            ExecutionError::MistypedParameter {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {expected} for parameter `${name}`, got {found}"
+            ),
+            ExecutionError::InvalidPattern(pattern, error) => {
+                write!(f, "invalid regular expression `{pattern}`: {error}")
+            }
+            ExecutionError::AmbiguousStep(step) => {
+                write!(f, "`{step}` names more than one step of the path")
+            }
+            ExecutionError::GroupedAll => {
+                write!(
+                    f,
+                    "`*` cannot be selected together with aggregates or `group by`"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+impl From<GraphError> for ExecutionError {
+    fn from(error: GraphError) -> Self {
+        ExecutionError::Graph(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransactionState {
+    Idle,
+    Active,
+    Aborted,
+}
+
+/// Runs parsed MQL statements against a [`Graph`].
+///
+/// Records written as `label:id` are nodes carrying `label` whose [`RECORD_ID`] property is `id`.
+///
+/// A `select` starts at every record matching the first step of its path, follows outgoing edges
+/// into records matching each further step, and keeps the matches of the whole path that pass
+/// the filter, one row each. Bare properties are read from the record at the end of the path,
+/// `step.property` from the record matched by the step labelled `step`. Expressions over a
+/// missing property evaluate to nothing, and comparisons with nothing or with a differently typed
+/// value are false, `not in` included; only `is null` holds for nothing. `null` is nothing as
+/// well, so creating a record with a `null` property leaves the property out.
+///
+/// `in` requires a list on its right. `contains` tests a list for an element or a string for a
+/// substring. `like` matches whole strings against a [`LikePattern`] and `ilike` does so ignoring
+/// case. These, `starts with`, `ends with` and `matches` (an unanchored regular expression
+/// search) hold for a list when they hold for any of its string elements. A `like` on a property
+/// of the path's source whose pattern starts with literal text scans only the matching range of
+/// the index of a unique constraint on the property, if there is one.
+///
+/// With aggregates, `group by` or `having`, the records are split into groups and every group
+/// becomes one row; without `group by`, all records form a single group.
+///
+/// Rows are sorted by `order by` with missing values last, or first when descending, and rows
+/// that tie keep the order of their nodes. `distinct`, `offset` and `limit` are applied to the
+/// sorted rows in that order.
+///
+/// Statements between `begin` and `commit` form one transaction, whose writes are staged on a
+/// copy of the graph until it commits. If any of them fails, the whole transaction is rolled
+/// back and every further statement is refused until `rollback`.
+pub struct Executor {
+    graph: Graph,
+    /// Copy of the graph the open transaction writes to.
+    staged: Option<Graph>,
+    transaction: TransactionState,
+    /// Regular expressions of `matches` by their source.
+    patterns: RefCell<HashMap<String, Regex>>,
+}
+
+impl Executor {
+    pub fn new(graph: Graph) -> Self {
+        Self {
+            graph,
+            staged: None,
+            transaction: TransactionState::Idle,
+            patterns: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The graph as statements see it, with the writes of the open transaction.
+    pub fn graph(&self) -> &Graph {
+        self.staged.as_ref().unwrap_or(&self.graph)
+    }
+
+    fn graph_mut(&mut self) -> &mut Graph {
+        self.staged.as_mut().unwrap_or(&mut self.graph)
+    }
+
+    /// Whether a transaction is open, including one that failed and still awaits `rollback`.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction != TransactionState::Idle
+    }
+
+    /// Returns the graph, rolling back a transaction that is still open.
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+
+    /// Executes a prepared statement with the values of its parameters.
+    pub fn execute_prepared(
+        &mut self,
+        statement: &Prepared,
+        parameters: &Parameters,
+    ) -> Result<Outcome, ExecutionError> {
+        self.execute(statement.bind(parameters)?)
+    }
+
+    pub fn execute(&mut self, statement: AST) -> Result<Outcome, ExecutionError> {
+        match (self.transaction, statement) {
+            (TransactionState::Idle, AST::Begin) => {
+                self.staged = Some(self.graph.clone());
+                self.transaction = TransactionState::Active;
+                Ok(Outcome::Began)
+            }
+            (_, AST::Begin) => Err(ExecutionError::TransactionAlreadyOpen),
+            (TransactionState::Active, AST::Commit) => {
+                self.graph = self
+                    .staged
+                    .take()
+                    .expect("an active transaction has a copy");
+                self.transaction = TransactionState::Idle;
+                Ok(Outcome::Committed)
+            }
+            (TransactionState::Aborted, AST::Commit) => Err(ExecutionError::TransactionAborted),
+            (TransactionState::Active | TransactionState::Aborted, AST::Rollback) => {
+                self.staged = None;
+                self.transaction = TransactionState::Idle;
+                Ok(Outcome::RolledBack)
+            }
+            (TransactionState::Idle, AST::Commit | AST::Rollback) => {
+                Err(ExecutionError::NoTransaction)
+            }
+            (TransactionState::Aborted, _) => Err(ExecutionError::TransactionAborted),
+            (TransactionState::Active, statement) => {
+                let result = self.execute_statement(statement);
+                if result.is_err() {
+                    self.staged = None;
+                    self.transaction = TransactionState::Aborted;
+                }
+                result
+            }
+            (TransactionState::Idle, statement) => self.execute_statement(statement),
+        }
+    }
+
+    fn execute_statement(&mut self, statement: AST) -> Result<Outcome, ExecutionError> {
+        match statement {
+            AST::Select(query) => self.select(*query),
+            AST::Create(entity, values) => self.create(entity, values),
+            AST::Link(from, label, to) => self.link(from, label, to),
+            AST::DefineLabel(label, properties) => {
+                self.graph_mut().define_label(&label, properties)?;
+                Ok(Outcome::Defined)
+            }
+            AST::DefineEdge(label, from, to) => {
+                self.graph_mut().define_edge(&label, &from, &to)?;
+                Ok(Outcome::Defined)
+            }
+            AST::CreateConstraint(constraint) => {
+                self.graph_mut().create_constraint(constraint)?;
+                Ok(Outcome::Defined)
+            }
+            AST::Begin | AST::Commit | AST::Rollback => {
+                unreachable!("transaction control is handled by execute")
+            }
+        }
+    }
+
+    fn select(&self, query: Query) -> Result<Outcome, ExecutionError> {
+        let Query {
+            distinct,
+            projection,
+            path,
+            filter,
+            group,
+            having,
+            order,
+            limit,
+            offset,
+        } = query;
+        let PathExpression(source, steps) = &path;
+        let labels = std::iter::once(source)
+            .chain(steps)
+            .map(label)
+            .collect::<Vec<_>>();
+
+        let fields = match &projection {
+            Projection::Fields(fields) => fields.as_slice(),
+            Projection::All => &[],
+        };
+        // `order by` may name a column by its alias
+        let order = order
+            .into_iter()
+            .map(|key| {
+                let field = match &key.expression {
+                    Expression::Property(name) => fields
+                        .iter()
+                        .find(|field| field.alias.as_ref() == Some(name)),
+                    _ => None,
+                };
+                match field {
+                    Some(field) => SortKey {
+                        expression: field.expression.clone(),
+                        direction: key.direction,
+                    },
+                    None => key,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // mistakes are reported even when no record reaches them
+        let projected = fields.iter().map(|field| &field.expression);
+        let sort_keys = order.iter().map(|key| &key.expression);
+        let mut check = |expression: &Expression| check_expression(expression, &labels);
+        projected
+            .clone()
+            .chain(&group)
+            .chain(sort_keys.clone())
+            .try_for_each(&mut check)?;
+        for filter in filter.iter().chain(&having) {
+            visit_filter(filter, &mut check)?;
+        }
+
+        if let Some(filter) = &filter {
+            visit_filter(filter, &mut |expression| {
+                refuse_aggregates(expression, "`where`")
+            })?;
+        }
+        group
+            .iter()
+            .try_for_each(|expression| refuse_aggregates(expression, "`group by`"))?;
+
+        let aggregated = !group.is_empty()
+            || having.is_some()
+            || projected
+                .clone()
+                .chain(sort_keys.clone())
+                .any(contains_aggregate);
+        if aggregated {
+            if let Projection::All = projection {
+                return Err(ExecutionError::GroupedAll);
+            }
+            let mut check = |expression: &Expression| check_grouped(expression, &group);
+            projected.chain(sort_keys).try_for_each(&mut check)?;
+            if let Some(having) = &having {
+                visit_filter(having, &mut check)?;
+            }
+        }
+
+        // every match of the path, as the ids of its nodes from source to end
+        let mut matches = self
+            .sources(source, &labels, filter.as_ref())
+            .filter(|node| self.matches_entity(node, source))
+            .map(|node| vec![node.id()])
+            .collect::<BTreeSet<_>>();
+        for step in steps {
+            matches = matches
+                .iter()
+                .flat_map(|ids| {
+                    let end = *ids.last().expect("paths start at their source");
+                    self.graph()
+                        .outgoing(end)
+                        .iter()
+                        .map(|edge| {
+                            self.graph()
+                                .get_node(edge.to())
+                                .expect("edges point at nodes")
+                        })
+                        .filter(|node| self.matches_entity(node, step))
+                        .map(|node| ids.iter().copied().chain([node.id()]).collect())
+                })
+                .collect();
+        }
+
+        let mut records = Vec::new();
+        for ids in matches {
+            let record = ids
+                .into_iter()
+                .map(|id| {
+                    self.graph()
+                        .get_node(id)
+                        .expect("ids are taken from the graph")
+                })
+                .collect::<Vec<_>>();
+            if let Some(filter) = &filter
+                && !self.matches_filter(&labels, &[&record], filter)?
+            {
+                continue;
+            }
+            records.push(record);
+        }
+        let records = records.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let (columns, expressions): (Vec<_>, Vec<_>) = match projection {
+            Projection::Fields(fields) => fields
+                .into_iter()
+                .map(|field| {
+                    let column = field.alias.unwrap_or_else(|| field.expression.to_string());
+                    (column, field.expression)
+                })
+                .unzip(),
+            Projection::All => {
+                let symbols = self.graph().symbols();
+                let keys = records
+                    .iter()
+                    .filter_map(|record| record.last())
+                    .flat_map(|node| node.properties().keys())
+                    .map(|&key| symbols.resolve(key))
+                    .collect::<BTreeSet<_>>();
+
+                // the record id leads, every other property follows in name order
+                let mut columns = Vec::with_capacity(keys.len());
+                if keys.contains(RECORD_ID) {
+                    columns.push(RECORD_ID.to_string());
+                }
+                columns.extend(
+                    keys.into_iter()
+                        .filter(|&key| key != RECORD_ID)
+                        .map(str::to_string),
+                );
+                columns
+                    .into_iter()
+                    .map(|column| (column.clone(), Expression::Property(column)))
+                    .unzip()
+            }
+        };
+
+        // without aggregates every record is a group of its own
+        let grouped;
+        let mut groups = match aggregated {
+            true => {
+                grouped = self.group(&labels, &records, &group)?;
+                grouped.iter().map(Vec::as_slice).collect::<Vec<_>>()
+            }
+            false => records.iter().map(std::slice::from_ref).collect(),
+        };
+        if let Some(having) = &having {
+            let mut kept = Vec::with_capacity(groups.len());
+            for group in groups {
+                if self.matches_filter(&labels, group, having)? {
+                    kept.push(group);
+                }
+            }
+            groups = kept;
+        }
+
+        let project = |group: &[&Record]| {
+            expressions
+                .iter()
+                .map(|expression| self.evaluate(&labels, group, expression))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(usize::MAX);
+
+        if order.is_empty() {
+            // rows keep the order of their records, so rows past the limit are never evaluated
+            let mut seen = HashSet::new();
+            let mut rows = Vec::new();
+            let mut skipped = 0;
+            for group in groups {
+                if rows.len() == limit {
+                    break;
+                }
+
+                let row = project(group)?;
+                if distinct && !seen.insert(row_key(&row)) {
+                    continue;
+                }
+                if skipped < offset {
+                    skipped += 1;
+                    continue;
+                }
+                rows.push(row);
+            }
+
+            return Ok(Outcome::Selected(Rows::new(columns, rows)));
+        }
+
+        // only the sort keys decide which groups are kept, so only those groups are projected
+        let mut ranked = Vec::with_capacity(groups.len());
+        for (position, group) in groups.into_iter().enumerate() {
+            let keys = order
+                .iter()
+                .map(|key| self.evaluate(&labels, group, &key.expression))
+                .collect::<Result<_, _>>()?;
+            ranked.push(Ranked {
+                keys,
+                position,
+                item: group,
+                order: &order,
+            });
+        }
+
+        let rows = if distinct {
+            // rows are told apart by their values, and of equal rows the one sorted first is kept
+            let mut first = HashMap::<_, Ranked<_>>::new();
+            for candidate in ranked {
+                let row = project(candidate.item)?;
+                let candidate = Ranked {
+                    keys: candidate.keys,
+                    position: candidate.position,
+                    item: row,
+                    order: candidate.order,
+                };
+                match first.entry(row_key(&candidate.item)) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(candidate);
+                    }
+                    Entry::Occupied(mut entry) => {
+                        if candidate < *entry.get() {
+                            entry.insert(candidate);
+                        }
+                    }
+                }
+            }
+
+            page(first.into_values().collect(), offset, limit)
+                .map(|ranked| ranked.item)
+                .collect()
+        } else {
+            page(ranked, offset, limit)
+                .map(|ranked| project(ranked.item))
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(Outcome::Selected(Rows::new(columns, rows)))
+    }
+
+    /// Nodes the source of a path may match. When the filter requires a property of the source
+    /// to be `like` a pattern starting with literal text, these come from an ordered index on the
+    /// property if there is one, and otherwise from every node.
+    fn sources<'g>(
+        &'g self,
+        source: &EntityDescription,
+        labels: &[&str],
+        filter: Option<&'g FilterExpression>,
+    ) -> Box<dyn Iterator<Item = &'g Node> + 'g> {
+        if let Some((property, prefix)) = filter.and_then(|filter| like_prefix(filter, labels))
+            && let Some(nodes) = self
+                .graph()
+                .nodes_with_prefix(label(source), property, &prefix)
+        {
+            return Box::new(nodes.collect::<Vec<_>>().into_iter());
+        }
+
+        Box::new(self.graph().nodes())
+    }
+
+    fn matches_entity(&self, node: &Node, entity: &EntityDescription) -> bool {
+        let (label, id) = match entity {
+            EntityDescription::NoId(label) => (label, None),
+            EntityDescription::WithId(id, label) => (label, Some(id)),
+        };
+
+        let has_label = self
+            .graph()
+            .symbols()
+            .get(label)
+            .is_some_and(|label| node.has_label(label));
+
+        has_label
+            && id.is_none_or(|id| {
+                matches!(self.property(node, RECORD_ID), Some(graph::Value::String(value)) if value == id)
+            })
+    }
+
+    /// Splits `records` into groups with equal values of the `group` expressions, in the order
+    /// their first records come in. Without expressions, all records form one group, even if
+    /// there are none.
+    fn group<'r, 'g>(
+        &self,
+        labels: &[&str],
+        records: &[&'r Record<'g>],
+        group: &[Expression],
+    ) -> Result<Vec<Vec<&'r Record<'g>>>, ExecutionError> {
+        if group.is_empty() {
+            return Ok(vec![records.to_vec()]);
+        }
+
+        let mut positions = HashMap::new();
+        let mut groups = Vec::<Vec<_>>::new();
+        for &record in records {
+            let values = group
+                .iter()
+                .map(|expression| self.evaluate(labels, &[record], expression))
+                .collect::<Result<Vec<_>, _>>()?;
+            match positions.entry(row_key(&values)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(groups.len());
+                    groups.push(vec![record]);
+                }
+                Entry::Occupied(entry) => groups[*entry.get()].push(record),
+            }
+        }
+
+        Ok(groups)
+    }
+
+    fn matches_filter(
+        &self,
+        labels: &[&str],
+        group: &[&Record],
+        filter: &FilterExpression,
+    ) -> Result<bool, ExecutionError> {
+        match filter {
+            FilterExpression::Not(filter) => Ok(!self.matches_filter(labels, group, filter)?),
+            FilterExpression::And(lhs, rhs) => Ok(self.matches_filter(labels, group, lhs)?
+                && self.matches_filter(labels, group, rhs)?),
+            FilterExpression::Or(lhs, rhs) => Ok(self.matches_filter(labels, group, lhs)?
+                || self.matches_filter(labels, group, rhs)?),
+            FilterExpression::Comparison(lhs, operator, rhs) => {
+                let (Some(lhs), Some(rhs)) = (
+                    self.evaluate(labels, group, lhs)?,
+                    self.evaluate(labels, group, rhs)?,
+                ) else {
+                    return Ok(false);
+                };
+
+                let equal = |lhs: &graph::Value, rhs: &graph::Value| {
+                    compare(lhs, rhs) == Some(Ordering::Equal)
+                };
+
+                match operator {
+                    Operator::In | Operator::NotIn => {
+                        let graph::Value::List(values) = &rhs else {
+                            return Err(ExecutionError::InvalidOperands(
+                                operator.to_string(),
+                                vec![lhs.type_name(), rhs.type_name()],
+                            ));
+                        };
+                        let found = values.iter().any(|value| equal(&lhs, value));
+
+                        Ok(found == (*operator == Operator::In))
+                    }
+                    Operator::Contains => Ok(match (&lhs, &rhs) {
+                        (graph::Value::List(values), rhs) => {
+                            values.iter().any(|value| equal(value, rhs))
+                        }
+                        (graph::Value::String(lhs), graph::Value::String(rhs)) => {
+                            lhs.contains(rhs.as_str())
+                        }
+                        _ => false,
+                    }),
+                    Operator::Like | Operator::ILike => {
+                        let Some(pattern) = text(&rhs) else {
+                            return Ok(false);
+                        };
+                        let pattern = LikePattern::new(pattern, *operator == Operator::ILike);
+                        Ok(any_element(&lhs, |value| {
+                            text(value).is_some_and(|value| pattern.is_match(value))
+                        }))
+                    }
+                    Operator::StartsWith | Operator::EndsWith => {
+                        let Some(pattern) = text(&rhs) else {
+                            return Ok(false);
+                        };
+                        Ok(any_element(&lhs, |value| {
+                            text(value).is_some_and(|value| match operator {
+                                Operator::StartsWith => value.starts_with(pattern),
+                                _ => value.ends_with(pattern),
+                            })
+                        }))
+                    }
+                    Operator::Matches => {
+                        let Some(pattern) = text(&rhs) else {
+                            return Ok(false);
+                        };
+                        let pattern = self.pattern(pattern)?;
+                        Ok(any_element(&lhs, |value| {
+                            text(value).is_some_and(|value| pattern.is_match(value))
+                        }))
+                    }
+                    _ => {
+                        let Some(ordering) = compare(&lhs, &rhs) else {
+                            return Ok(false);
+                        };
+
+                        Ok(match operator {
+                            Operator::Equals => ordering == Ordering::Equal,
+                            Operator::NotEquals => ordering != Ordering::Equal,
+                            Operator::SmallerThan => ordering == Ordering::Less,
+                            Operator::GreaterThan => ordering == Ordering::Greater,
+                            Operator::SmallerThanOrEqual => ordering != Ordering::Greater,
+                            Operator::GreaterThanOrEqual => ordering != Ordering::Less,
+                            _ => unreachable!("handled above"),
+                        })
+                    }
+                }
+            }
+            FilterExpression::IsNull(expression) => {
+                Ok(self.evaluate(labels, group, expression)?.is_none())
+            }
+            FilterExpression::IsNotNull(expression) => {
+                Ok(self.evaluate(labels, group, expression)?.is_some())
+            }
+        }
+    }
+
+    /// The regular expression `source`, compiled once for all the queries using it.
+    fn pattern(&self, source: &str) -> Result<Regex, ExecutionError> {
+        if let Some(pattern) = self.patterns.borrow().get(source) {
+            return Ok(pattern.clone());
+        }
+
+        let pattern = Regex::new(source).map_err(|error| {
+            ExecutionError::InvalidPattern(source.to_string(), error.to_string())
+        })?;
+        self.patterns
+            .borrow_mut()
+            .insert(source.to_string(), pattern.clone());
+
+        Ok(pattern)
+    }
+
+    /// Value of `expression` for a group of records, or `None` if it reads a property that is
+    /// missing.
+    ///
+    /// Aggregates run over the whole group and properties are read from its first record, as
+    /// they are the same for every record of a group. Outside of aggregating queries, each
+    /// record is a group of its own.
+    ///
+    /// Bare properties are read from the node the path ends at, and `step.property` from the
+    /// node matched by the step `labels` names `step`. Other accesses read entries of maps.
+    fn evaluate(
+        &self,
+        labels: &[&str],
+        group: &[&Record],
+        expression: &Expression,
+    ) -> Result<Option<graph::Value>, ExecutionError> {
+        match expression {
+            Expression::Literal(value) => convert(value.clone()),
+            Expression::Property(key) => Ok(group
+                .first()
+                .and_then(|record| record.last())
+                .and_then(|node| self.property(node, key))
+                .cloned()),
+            Expression::Access(target, name) => {
+                if let Expression::Property(step) = &**target
+                    && let Some(index) = labels.iter().position(|label| label == step)
+                {
+                    return Ok(group
+                        .first()
+                        .and_then(|record| self.property(record[index], name))
+                        .cloned());
+                }
+
+                match self.evaluate(labels, group, target)? {
+                    Some(graph::Value::Map(mut entries)) => Ok(entries.remove(name)),
+                    _ => Ok(None),
+                }
+            }
+            Expression::Aggregate(function, distinct, argument) => {
+                let Some(argument) = argument else {
+                    return Ok(Some(graph::Value::Int(group.len() as i64)));
+                };
+
+                let mut values = Vec::with_capacity(group.len());
+                let mut seen = HashSet::new();
+                for record in group {
+                    if let Some(value) =
+                        self.evaluate(labels, std::slice::from_ref(record), argument)?
+                        && (!distinct || seen.insert(IndexKey::from(&value)))
+                    {
+                        values.push(value);
+                    }
+                }
+
+                aggregate(*function, values)
+            }
+            Expression::Negate(operand) => match self.evaluate(labels, group, operand)? {
+                None => Ok(None),
+                Some(graph::Value::Int(int)) => int
+                    .checked_neg()
+                    .map(|int| Some(graph::Value::Int(int)))
+                    .ok_or(ExecutionError::Overflow),
+                Some(graph::Value::Float(float)) => Ok(Some(graph::Value::Float(-float))),
+                Some(value) => Err(ExecutionError::InvalidOperands(
+                    "-".to_string(),
+                    vec![value.type_name()],
+                )),
+            },
+            Expression::Arithmetic(lhs, operator, rhs) => {
+                let (Some(lhs), Some(rhs)) = (
+                    self.evaluate(labels, group, lhs)?,
+                    self.evaluate(labels, group, rhs)?,
+                ) else {
+                    return Ok(None);
+                };
+
+                arithmetic(lhs, *operator, rhs).map(Some)
+            }
+            Expression::Call(function, arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    match self.evaluate(labels, group, argument)? {
+                        Some(value) => values.push(value),
+                        None => return Ok(None),
+                    }
+                }
+
+                call(function, values).map(Some)
+            }
+        }
+    }
+
+    fn property<'n>(&self, node: &'n Node, key: &str) -> Option<&'n graph::Value> {
+        node.property(self.graph().symbols().get(key)?)
+    }
+
+    fn create(
+        &mut self,
+        entity: EntityDescription,
+        values: HashMap<String, Value>,
+    ) -> Result<Outcome, ExecutionError> {
+        let mut properties = values
+            .into_iter()
+            .filter_map(|(key, value)| convert(value).map(|value| Some((key, value?))).transpose())
+            .collect::<Result<HashMap<_, _>, ExecutionError>>()?;
+
+        let label = match entity {
+            EntityDescription::NoId(label) => label,
+            EntityDescription::WithId(id, label) => {
+                if self.find_record(&label, &id).is_some() {
+                    return Err(ExecutionError::DuplicateRecord(label, id));
+                }
+
+                properties.insert(RECORD_ID.to_string(), graph::Value::String(id));
+                label
+            }
+        };
+
+        let id = self.graph_mut().add_node(vec![label], properties)?;
+
+        Ok(Outcome::Created(id))
+    }
+
+    /// Links two records; the edge is labelled after the target unless named explicitly.
+    fn link(
+        &mut self,
+        from: EntityDescription,
+        label: Option<String>,
+        to: EntityDescription,
+    ) -> Result<Outcome, ExecutionError> {
+        let (from, _) = self.resolve_record(from)?;
+        let (to, to_label) = self.resolve_record(to)?;
+
+        self.graph_mut()
+            .add_edge(from, to, label.unwrap_or(to_label))?;
+
+        Ok(Outcome::Linked)
+    }
+
+    fn resolve_record(&self, entity: EntityDescription) -> Result<(usize, String), ExecutionError> {
+        match entity {
+            EntityDescription::NoId(label) => Err(ExecutionError::MissingRecordId(label)),
+            EntityDescription::WithId(id, label) => match self.find_record(&label, &id) {
+                Some(node) => Ok((node, label)),
+                None => Err(ExecutionError::RecordNotFound(label, id)),
+            },
+        }
+    }
+
+    fn find_record(&self, label: &str, id: &str) -> Option<usize> {
+        let key = self.graph().symbols().get(RECORD_ID)?;
+        self.graph()
+            .nodes_with_label(label)
+            .find(|node| matches!(node.property(key), Some(graph::Value::String(value)) if value == id))
+            .map(|node| node.id())
+    }
+}
+
+/// Orders two values of the same type, comparing ints and floats numerically.
+///
+/// Lists are ordered element by element and maps entry by entry, like strings are by character.
+fn compare(lhs: &graph::Value, rhs: &graph::Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (graph::Value::String(lhs), graph::Value::String(rhs)) => Some(lhs.cmp(rhs)),
+        (graph::Value::Int(lhs), graph::Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+        (graph::Value::Int(lhs), graph::Value::Float(rhs)) => compare_int_float(*lhs, *rhs),
+        (graph::Value::Float(lhs), graph::Value::Int(rhs)) => {
+            compare_int_float(*rhs, *lhs).map(Ordering::reverse)
+        }
+        (graph::Value::Float(lhs), graph::Value::Float(rhs)) => lhs.partial_cmp(rhs),
+        (graph::Value::Bool(lhs), graph::Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+        (graph::Value::List(lhs), graph::Value::List(rhs)) => {
+            for (lhs, rhs) in lhs.iter().zip(rhs) {
+                match compare(lhs, rhs)? {
+                    Ordering::Equal => {}
+                    ordering => return Some(ordering),
+                }
+            }
+            Some(lhs.len().cmp(&rhs.len()))
+        }
+        (graph::Value::Map(lhs), graph::Value::Map(rhs)) => {
+            for ((lhs_key, lhs), (rhs_key, rhs)) in lhs.iter().zip(rhs) {
+                match lhs_key.cmp(rhs_key).then(compare(lhs, rhs)?) {
+                    Ordering::Equal => {}
+                    ordering => return Some(ordering),
+                }
+            }
+            Some(lhs.len().cmp(&rhs.len()))
+        }
+        _ => None,
+    }
+}
+
+/// Orders an int and a float exactly, where converting the int to a float could round it.
+fn compare_int_float(int: i64, float: f64) -> Option<Ordering> {
+    // `i64::MAX as f64` rounds up to 2^63, past every int, while `i64::MIN as f64` is exact
+    if float.is_nan() {
+        None
+    } else if float >= i64::MAX as f64 {
+        Some(Ordering::Less)
+    } else if float < i64::MIN as f64 {
+        Some(Ordering::Greater)
+    } else {
+        match int.cmp(&(float.floor() as i64)) {
+            Ordering::Equal if float.fract() != 0.0 => Some(Ordering::Less),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// The contents of `value` if it is a string.
+fn text(value: &graph::Value) -> Option<&str> {
+    match value {
+        graph::Value::String(string) => Some(string),
+        _ => None,
+    }
+}
+
+/// Whether `test` holds for `value`, or for any of its elements if it is a list.
+fn any_element(value: &graph::Value, test: impl Fn(&graph::Value) -> bool) -> bool {
+    match value {
+        graph::Value::List(values) => values.iter().any(test),
+        value => test(value),
+    }
+}
+
+/// Nodes matched by the steps of a path, from its source to the node the path ends at.
+type Record<'g> = [&'g Node];
+
+fn label(entity: &EntityDescription) -> &str {
+    match entity {
+        EntityDescription::NoId(label) | EntityDescription::WithId(_, label) => label,
+    }
+}
+
+/// Property of the source of a path that `filter` requires to be `like` a pattern, and the
+/// literal text the pattern starts with, if any.
+fn like_prefix<'f>(filter: &'f FilterExpression, labels: &[&str]) -> Option<(&'f str, String)> {
+    match filter {
+        FilterExpression::And(lhs, rhs) => {
+            like_prefix(lhs, labels).or_else(|| like_prefix(rhs, labels))
+        }
+        FilterExpression::Comparison(
+            lhs,
+            Operator::Like,
+            Expression::Literal(Value::String(pattern)),
+        ) => {
+            let property = match lhs {
+                // bare properties are read from the end of the path
+                Expression::Property(property) if labels.len() == 1 => property,
+                Expression::Access(step, property) if matches!(&**step, Expression::Property(step) if step == labels[0]) => {
+                    property
+                }
+                _ => return None,
+            };
+            let prefix = LikePattern::new(pattern, false).prefix();
+
+            (!prefix.is_empty()).then_some((property.as_str(), prefix))
+        }
+        _ => None,
+    }
+}
+
+/// Total order of values for sorting rows.
+///
+/// Values of the same type are ordered like [`compare`] orders them, with NaN after every other
+/// number. Values of different types are ordered by type: bools, numbers, strings, lists, maps.
+fn sort_order(lhs: &graph::Value, rhs: &graph::Value) -> Ordering {
+    fn rank(value: &graph::Value) -> u8 {
+        match value {
+            graph::Value::Bool(_) => 0,
+            graph::Value::Int(_) | graph::Value::Float(_) => 1,
+            graph::Value::String(_) => 2,
+            graph::Value::List(_) => 3,
+            graph::Value::Map(_) => 4,
+        }
+    }
+
+    fn is_nan(value: &graph::Value) -> bool {
+        matches!(value, graph::Value::Float(float) if float.is_nan())
+    }
+
+    match (lhs, rhs) {
+        (graph::Value::List(lhs), graph::Value::List(rhs)) => lhs
+            .iter()
+            .zip(rhs)
+            .map(|(lhs, rhs)| sort_order(lhs, rhs))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+        (graph::Value::Map(lhs), graph::Value::Map(rhs)) => lhs
+            .iter()
+            .zip(rhs)
+            .map(|((lhs_key, lhs), (rhs_key, rhs))| {
+                lhs_key.cmp(rhs_key).then_with(|| sort_order(lhs, rhs))
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+        _ if rank(lhs) != rank(rhs) => rank(lhs).cmp(&rank(rhs)),
+        // numbers only fail to compare when one of them is NaN
+        _ => compare(lhs, rhs).unwrap_or_else(|| is_nan(lhs).cmp(&is_nan(rhs))),
+    }
+}
+
+/// Group of records of an ordered `select`, or the row projected from it, together with the
+/// values it is sorted by.
+struct Ranked<'q, T> {
+    keys: Vec<Option<graph::Value>>,
+    /// Position of the group among the matches, which breaks ties.
+    position: usize,
+    item: T,
+    order: &'q [SortKey],
+}
+
+/// Sorts by every key in turn; a missing value sorts after all others in ascending order.
+impl<T> Ord for Ranked<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .iter()
+            .zip(self.keys.iter().zip(&other.keys))
+            .map(|(key, (lhs, rhs))| {
+                let ordering = match (lhs, rhs) {
+                    (Some(lhs), Some(rhs)) => sort_order(lhs, rhs),
+                    (lhs, rhs) => lhs.is_none().cmp(&rhs.is_none()),
+                };
+                match key.direction {
+                    Direction::Ascending => ordering,
+                    Direction::Descending => ordering.reverse(),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then(self.position.cmp(&other.position))
+    }
+}
+
+impl<T> PartialOrd for Ranked<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Ranked<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<'_, T> {}
+
+/// Items `offset..offset + limit` of `items` in sorted order, sorting only as many as needed.
+fn page<T: Ord>(mut items: Vec<T>, offset: usize, limit: usize) -> impl Iterator<Item = T> {
+    match offset.checked_add(limit) {
+        Some(count) if count < items.len() => items = top(items, count),
+        _ => items.sort_unstable(),
+    }
+
+    items.into_iter().skip(offset).take(limit)
+}
+
+/// The `count` smallest items in order, keeping no more than `count` of them on a heap.
+fn top<T: Ord>(items: impl IntoIterator<Item = T>, count: usize) -> Vec<T> {
+    let mut heap = BinaryHeap::with_capacity(count);
+    for item in items {
+        if heap.len() < count {
+            heap.push(item);
+        } else if let Some(mut largest) = heap.peek_mut()
+            && item < *largest
+        {
+            *largest = item;
+        }
+    }
+
+    heap.into_sorted_vec()
+}
+
+/// What tells a row apart from the others for `select distinct`.
+fn row_key(row: &[Option<graph::Value>]) -> Vec<Option<IndexKey>> {
+    row.iter()
+        .map(|value| value.as_ref().map(IndexKey::from))
+        .collect()
+}
+
+/// Converts a literal into the value stored for it, which is none for `null`.
+///
+/// Entries of nested maps that are `null` are left out.
+fn convert(value: Value) -> Result<Option<graph::Value>, ExecutionError> {
+    Ok(Some(match value {
+        Value::Null => return Ok(None),
+        Value::Parameter(name) => return Err(ExecutionError::MissingParameter(name)),
+        Value::String(string) => graph::Value::String(string),
+        Value::Int(int) => graph::Value::Int(int),
+        Value::Float(float) => graph::Value::Float(float),
+        Value::Bool(bool) => graph::Value::Bool(bool),
+        Value::List(values) => graph::Value::List(
+            values
+                .into_iter()
+                .map(|value| convert(value)?.ok_or(ExecutionError::NullInList))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => graph::Value::Map(
+            entries
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    convert(value).map(|value| Some((key, value?))).transpose()
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    }))
+}
+
+/// Functions callable in expressions, with the number of arguments each takes.
+const FUNCTIONS: &[(&str, usize)] = &[("lower", 1), ("upper", 1), ("len", 1), ("abs", 1)];
+
+/// Checks the functions `expression` calls and the path steps, labelled `labels`, it reads from.
+fn check_expression(expression: &Expression, labels: &[&str]) -> Result<(), ExecutionError> {
+    match expression {
+        Expression::Literal(value) => check_bound(value),
+        Expression::Property(_) => Ok(()),
+        Expression::Negate(operand) => check_expression(operand, labels),
+        Expression::Arithmetic(lhs, _, rhs) => {
+            check_expression(lhs, labels)?;
+            check_expression(rhs, labels)
+        }
+        Expression::Call(function, arguments) => {
+            let Some(&(_, expected)) = FUNCTIONS.iter().find(|(name, _)| name == function) else {
+                return Err(ExecutionError::UnknownFunction(function.clone()));
+            };
+            if arguments.len() != expected {
+                return Err(ExecutionError::WrongArgumentCount {
+                    function: function.clone(),
+                    expected,
+                    found: arguments.len(),
+                });
+            }
+
+            arguments
+                .iter()
+                .try_for_each(|argument| check_expression(argument, labels))
+        }
+        Expression::Aggregate(_, _, argument) => argument
+            .as_deref()
+            .map_or(Ok(()), |argument| check_expression(argument, labels)),
+        Expression::Access(target, _) => {
+            if let Expression::Property(step) = &**target
+                && labels.iter().filter(|label| *label == step).count() > 1
+            {
+                return Err(ExecutionError::AmbiguousStep(step.clone()));
+            }
+
+            check_expression(target, labels)
+        }
+    }
+}
+
+/// Refuses parameters left in `value`, which only [`Prepared::bind`] gives values.
+fn check_bound(value: &Value) -> Result<(), ExecutionError> {
+    match value {
+        Value::Parameter(name) => Err(ExecutionError::MissingParameter(name.clone())),
+        Value::List(values) => values.iter().try_for_each(check_bound),
+        Value::Map(entries) => entries.values().try_for_each(check_bound),
+        _ => Ok(()),
+    }
+}
+
+/// Calls `visit` with every expression compared in `filter`.
+fn visit_filter(
+    filter: &FilterExpression,
+    visit: &mut impl FnMut(&Expression) -> Result<(), ExecutionError>,
+) -> Result<(), ExecutionError> {
+    match filter {
+        FilterExpression::Comparison(lhs, _, rhs) => {
+            visit(lhs)?;
+            visit(rhs)
+        }
+        FilterExpression::IsNull(expression) | FilterExpression::IsNotNull(expression) => {
+            visit(expression)
+        }
+        FilterExpression::Not(filter) => visit_filter(filter, visit),
+        FilterExpression::And(lhs, rhs) | FilterExpression::Or(lhs, rhs) => {
+            visit_filter(lhs, visit)?;
+            visit_filter(rhs, visit)
+        }
+    }
+}
+
+fn contains_aggregate(expression: &Expression) -> bool {
+    match expression {
+        Expression::Aggregate(..) => true,
+        Expression::Literal(_) | Expression::Property(_) => false,
+        Expression::Negate(operand) | Expression::Access(operand, _) => contains_aggregate(operand),
+        Expression::Arithmetic(lhs, _, rhs) => contains_aggregate(lhs) || contains_aggregate(rhs),
+        Expression::Call(_, arguments) => arguments.iter().any(contains_aggregate),
+    }
+}
+
+fn refuse_aggregates(expression: &Expression, place: &'static str) -> Result<(), ExecutionError> {
+    match contains_aggregate(expression) {
+        true => Err(ExecutionError::MisplacedAggregate(place)),
+        false => Ok(()),
+    }
+}
+
+/// Checks that `expression` has the same value for every record of a group: it only reads
+/// properties inside aggregates or through the `group` expressions.
+fn check_grouped(expression: &Expression, group: &[Expression]) -> Result<(), ExecutionError> {
+    if group.contains(expression) {
+        return Ok(());
+    }
+
+    match expression {
+        Expression::Literal(_) => Ok(()),
+        Expression::Property(property) => Err(ExecutionError::UngroupedProperty(property.clone())),
+        Expression::Aggregate(_, _, argument) => match argument {
+            Some(argument) => refuse_aggregates(argument, "another aggregate"),
+            None => Ok(()),
+        },
+        Expression::Negate(operand) => check_grouped(operand, group),
+        // an ungrouped `author.name` is reported whole rather than as the step `author`
+        Expression::Access(target, _) => match check_grouped(target, group) {
+            Err(ExecutionError::UngroupedProperty(_)) => {
+                Err(ExecutionError::UngroupedProperty(expression.to_string()))
+            }
+            result => result,
+        },
+        Expression::Arithmetic(lhs, _, rhs) => {
+            check_grouped(lhs, group)?;
+            check_grouped(rhs, group)
+        }
+        Expression::Call(_, arguments) => arguments
+            .iter()
+            .try_for_each(|argument| check_grouped(argument, group)),
+    }
+}
+
+/// Applies `operator` to two numbers, or concatenates two strings with `+`.
+///
+/// Ints stay ints and fail on overflow; an int mixed with a float is widened to a float.
+fn arithmetic(
+    lhs: graph::Value,
+    operator: ArithmeticOperator,
+    rhs: graph::Value,
+) -> Result<graph::Value, ExecutionError> {
+    match (lhs, rhs) {
+        (graph::Value::Int(lhs), graph::Value::Int(rhs)) => {
+            let result = match operator {
+                ArithmeticOperator::Add => lhs.checked_add(rhs),
+                ArithmeticOperator::Subtract => lhs.checked_sub(rhs),
+                ArithmeticOperator::Multiply => lhs.checked_mul(rhs),
+                ArithmeticOperator::Divide | ArithmeticOperator::Remainder if rhs == 0 => {
+                    return Err(ExecutionError::DivisionByZero);
+                }
+                ArithmeticOperator::Divide => lhs.checked_div(rhs),
+                ArithmeticOperator::Remainder => lhs.checked_rem(rhs),
+            };
+
+            result
+                .map(graph::Value::Int)
+                .ok_or(ExecutionError::Overflow)
+        }
+        (graph::Value::String(lhs), graph::Value::String(rhs))
+            if operator == ArithmeticOperator::Add =>
+        {
+            Ok(graph::Value::String(lhs + &rhs))
+        }
+        (lhs, rhs) => {
+            let (Some(lhs_float), Some(rhs_float)) = (as_float(&lhs), as_float(&rhs)) else {
+                return Err(ExecutionError::InvalidOperands(
+                    operator.to_string(),
+                    vec![lhs.type_name(), rhs.type_name()],
+                ));
+            };
+
+            Ok(graph::Value::Float(match operator {
+                ArithmeticOperator::Add => lhs_float + rhs_float,
+                ArithmeticOperator::Subtract => lhs_float - rhs_float,
+                ArithmeticOperator::Multiply => lhs_float * rhs_float,
+                ArithmeticOperator::Divide => lhs_float / rhs_float,
+                ArithmeticOperator::Remainder => lhs_float % rhs_float,
+            }))
+        }
+    }
+}
+
+fn as_float(value: &graph::Value) -> Option<f64> {
+    match value {
+        graph::Value::Int(int) => Some(*int as f64),
+        graph::Value::Float(float) => Some(*float),
+        _ => None,
+    }
+}
+
+/// Aggregates the values of a group that are not missing.
+///
+/// Over no values, `sum`, `avg`, `min` and `max` are missing as well. `min` and `max` order
+/// values like `order by` does.
+fn aggregate(
+    function: AggregateFunction,
+    values: Vec<graph::Value>,
+) -> Result<Option<graph::Value>, ExecutionError> {
+    if let AggregateFunction::Sum | AggregateFunction::Avg = function
+        && let Some(value) = values.iter().find(|value| as_float(value).is_none())
+    {
+        return Err(ExecutionError::InvalidOperands(
+            format!("{function}()"),
+            vec![value.type_name()],
+        ));
+    }
+
+    Ok(match function {
+        AggregateFunction::Count => Some(graph::Value::Int(values.len() as i64)),
+        AggregateFunction::Collect => Some(graph::Value::List(values)),
+        AggregateFunction::Min => values.into_iter().min_by(sort_order),
+        AggregateFunction::Max => values.into_iter().max_by(sort_order),
+        AggregateFunction::Sum => {
+            let mut values = values.into_iter();
+            let Some(first) = values.next() else {
+                return Ok(None);
+            };
+            Some(values.try_fold(first, |sum, value| {
+                arithmetic(sum, ArithmeticOperator::Add, value)
+            })?)
+        }
+        AggregateFunction::Avg => match values.len() {
+            0 => None,
+            count => {
+                let sum = values.iter().filter_map(as_float).sum::<f64>();
+                Some(graph::Value::Float(sum / count as f64))
+            }
+        },
+    })
+}
+
+/// Calls one of the [`FUNCTIONS`], whose arity has already been checked.
+fn call(function: &str, arguments: Vec<graph::Value>) -> Result<graph::Value, ExecutionError> {
+    let invalid = |arguments: &[graph::Value]| {
+        ExecutionError::InvalidOperands(
+            format!("{function}()"),
+            arguments.iter().map(graph::Value::type_name).collect(),
+        )
+    };
+
+    match (function, arguments.as_slice()) {
+        ("lower", [graph::Value::String(string)]) => {
+            Ok(graph::Value::String(string.to_lowercase()))
+        }
+        ("upper", [graph::Value::String(string)]) => {
+            Ok(graph::Value::String(string.to_uppercase()))
+        }
+        ("len", [graph::Value::List(values)]) => Ok(graph::Value::Int(values.len() as i64)),
+        ("len", [graph::Value::String(string)]) => {
+            Ok(graph::Value::Int(string.chars().count() as i64))
+        }
+        ("abs", [graph::Value::Int(int)]) => int
+            .checked_abs()
+            .map(graph::Value::Int)
+            .ok_or(ExecutionError::Overflow),
+        ("abs", [graph::Value::Float(float)]) => Ok(graph::Value::Float(float.abs())),
+        _ => Err(invalid(&arguments)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mql::parser::Parser;
+
+    fn execute(executor: &mut Executor, statement: &str) -> Result<Outcome, ExecutionError> {
+        executor.execute(Parser::parse_one(statement).expect("test statements parse"))
+    }
+
+    fn run(executor: &mut Executor, statements: &[&str]) {
+        for statement in statements {
+            if let Err(error) = execute(executor, statement) {
+                panic!("`{statement}` failed: {error}");
+            }
+        }
+    }
+
+    fn select(executor: &mut Executor, query: &str) -> Vec<Vec<Option<graph::Value>>> {
+        match execute(executor, query) {
+            Ok(Outcome::Selected(rows)) => rows.rows().to_vec(),
+            other => panic!("`{query}` did not select: {other:?}"),
+        }
+    }
+
+    fn titles(executor: &mut Executor) -> Vec<Vec<Option<graph::Value>>> {
+        select(executor, "select title from book order by title")
+    }
+
+    fn string(value: &str) -> Option<graph::Value> {
+        Some(graph::Value::String(value.to_string()))
+    }
+
+    #[test]
+    fn rollback_discards_the_transaction() {
+        let mut executor = Executor::new(Graph::new());
+        run(&mut executor, &["create book:dune { title = \"Dune\" }"]);
+        let bound = executor.graph().id_bound();
+
+        run(
+            &mut executor,
+            &[
+                "begin",
+                "create book:emma { title = \"Emma\" }",
+                "link book:dune -> sequel -> book:emma",
+                "define label book { title: string required }",
+                "create constraint unique on book(title)",
+                "create fresh:x { n = 1 }",
+            ],
+        );
+        assert_eq!(titles(&mut executor), [[string("Dune")], [string("Emma")]]);
+        // a label first used in the transaction is only known to the staged graph
+        assert_eq!(select(&mut executor, "select n from fresh"), [[int(1)]]);
+        assert!(matches!(
+            execute(&mut executor, "rollback"),
+            Ok(Outcome::RolledBack)
+        ));
+
+        assert_eq!(titles(&mut executor), [[string("Dune")]]);
+        assert!(select(&mut executor, "select n from fresh").is_empty());
+        assert_eq!(executor.graph().edge_count(), 0);
+        assert_eq!(executor.graph().catalog().labels().count(), 0);
+        assert_eq!(executor.graph().constraints().count(), 0);
+        assert_eq!(executor.graph().id_bound(), bound);
+        assert!(!executor.in_transaction());
+    }
+
+    #[test]
+    fn commit_keeps_the_transaction() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "begin",
+                "create book:dune { title = \"Dune\" }",
+                "create constraint unique on book(title)",
+                "commit",
+            ],
+        );
+
+        assert_eq!(titles(&mut executor), [[string("Dune")]]);
+        assert_eq!(executor.into_graph().constraints().count(), 1);
+    }
+
+    #[test]
+    fn failed_statement_aborts_the_transaction_until_rollback() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &["begin", "create book:dune { title = \"Dune\" }"],
+        );
+
+        assert!(matches!(
+            execute(&mut executor, "link book:dune -> book:missing"),
+            Err(ExecutionError::RecordNotFound(..))
+        ));
+        // the writes before the failure are gone already
+        assert!(executor.graph().nodes().next().is_none());
+        for statement in ["create book:emma {}", "select * from book", "commit"] {
+            assert!(
+                matches!(
+                    execute(&mut executor, statement),
+                    Err(ExecutionError::TransactionAborted)
+                ),
+                "`{statement}` ran in an aborted transaction"
+            );
+        }
+        assert!(executor.in_transaction());
+
+        run(
+            &mut executor,
+            &["rollback", "create book:emma { title = \"Emma\" }"],
+        );
+        assert_eq!(titles(&mut executor), [[string("Emma")]]);
+    }
+
+    #[test]
+    fn into_graph_drops_an_open_transaction() {
+        let mut executor = Executor::new(Graph::new());
+        run(&mut executor, &["begin", "create book:dune {}"]);
+
+        assert_eq!(executor.into_graph().node_count(), 0);
+    }
+
+    fn int(value: i64) -> Option<graph::Value> {
+        Some(graph::Value::Int(value))
+    }
+
+    fn library() -> Executor {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create book:a { title = \"A\", pages = 300, genre = \"sf\" }",
+                "create book:b { title = \"B\", pages = 120, genre = \"sf\" }",
+                "create book:c { title = \"C\", pages = 120, genre = \"ya\" }",
+                "create book:d { title = \"D\", pages = 450, genre = \"ya\" }",
+                "create book:e { title = \"E\", genre = \"sf\" }",
+            ],
+        );
+
+        executor
+    }
+
+    fn column(executor: &mut Executor, query: &str) -> Vec<Option<graph::Value>> {
+        select(executor, query)
+            .into_iter()
+            .map(|row| row.into_iter().next().expect("one column"))
+            .collect()
+    }
+
+    fn strings(values: &[&str]) -> Vec<Option<graph::Value>> {
+        values.iter().map(|value| string(value)).collect()
+    }
+
+    fn prepared(
+        executor: &mut Executor,
+        statement: &str,
+        parameters: &[(&str, graph::Value)],
+    ) -> Result<Outcome, ExecutionError> {
+        let statement = Prepared::new(statement).expect("test statements parse");
+        let parameters = parameters
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        executor.execute_prepared(&statement, &parameters)
+    }
+
+    #[test]
+    fn prepared_statements_take_the_values_of_their_parameters() {
+        let mut executor = library();
+        let query = "select title, $tag from book where pages > $min order by title";
+        for min in [graph::Value::Int(200), graph::Value::Float(200.5)] {
+            let tag = ("tag", graph::Value::String("x".to_string()));
+            let Ok(Outcome::Selected(rows)) = prepared(&mut executor, query, &[("min", min), tag])
+            else {
+                panic!("`{query}` did not select");
+            };
+            assert_eq!(rows.columns(), ["title", "$tag"]);
+            assert_eq!(
+                rows.rows(),
+                [[string("A"), string("x")], [string("D"), string("x")]]
+            );
+        }
+
+        let create = "create book:f { title = $title, pages = $pages }";
+        let title = ("title", graph::Value::String("F \"1\"; --".to_string()));
+        prepared(
+            &mut executor,
+            create,
+            &[title, ("pages", graph::Value::Int(10))],
+        )
+        .unwrap();
+        assert_eq!(
+            column(&mut executor, "select title from book where pages < 100"),
+            strings(&["F \"1\"; --"])
+        );
+
+        // strings are ordered like when written in the statement
+        let query = "select title from book where title < $p order by title";
+        let p = ("p", graph::Value::String("C".to_string()));
+        let Ok(Outcome::Selected(rows)) = prepared(&mut executor, query, &[p]) else {
+            panic!("`{query}` did not select");
+        };
+        assert_eq!(rows.rows(), [[string("A")], [string("B")]]);
+    }
+
+    #[test]
+    fn parameters_without_values_are_errors() {
+        let mut executor = library();
+        let query = "select title from book where pages > $min and genre = $genre";
+        let min = ("min", graph::Value::Int(200));
+
+        for (parameters, missing) in [(&[][..], "min"), (std::slice::from_ref(&min), "genre")] {
+            match prepared(&mut executor, query, parameters) {
+                Err(ExecutionError::MissingParameter(name)) => assert_eq!(name, missing),
+                other => panic!("expected `${missing}` to be missing, got {other:?}"),
+            }
+        }
+        let unused = ("unused", graph::Value::Bool(true));
+        let genre = ("genre", graph::Value::String("sf".to_string()));
+        assert!(prepared(&mut executor, query, &[min, genre, unused]).is_ok());
+    }
+
+    #[test]
+    fn parameters_of_the_wrong_type_are_errors() {
+        let mut executor = library();
+        let list = graph::Value::List(vec![graph::Value::Int(1)]);
+        let text = graph::Value::String("200".to_string());
+
+        for (statement, value, expected, found) in [
+            (
+                "select title from book where pages <= $p",
+                list.clone(),
+                "number or string",
+                "list",
+            ),
+            (
+                "select title from book where $p < pages",
+                graph::Value::Bool(true),
+                "number or string",
+                "bool",
+            ),
+            (
+                "select title from book where title like $p",
+                graph::Value::Int(1),
+                "string",
+                "int",
+            ),
+            (
+                "select title from book where title matches $p",
+                list.clone(),
+                "string",
+                "list",
+            ),
+            (
+                "select title from book where pages in $p",
+                text,
+                "list",
+                "string",
+            ),
+        ] {
+            match prepared(&mut executor, statement, &[("p", value)]) {
+                Err(ExecutionError::MistypedParameter {
+                    name,
+                    expected: wanted,
+                    found: got,
+                }) => {
+                    assert_eq!(
+                        (name.as_str(), wanted, got),
+                        ("p", expected, found),
+                        "{statement}"
+                    )
+                }
+                other => panic!("`{statement}` took a {found}: {other:?}"),
+            }
+        }
+        // equality takes any value, which simply matches nothing of another type
+        let query = "select title from book where pages = $p";
+        let Ok(Outcome::Selected(rows)) = prepared(&mut executor, query, &[("p", list)]) else {
+            panic!("`{query}` did not select");
+        };
+        assert!(rows.rows().is_empty());
+    }
+
+    #[test]
+    fn order_sorts_by_every_key_with_missing_values_last() {
+        let mut executor = library();
+
+        assert_eq!(
+            column(
+                &mut executor,
+                "select title from book order by pages, title"
+            ),
+            strings(&["B", "C", "A", "D", "E"])
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select title from book order by pages desc, title desc"
+            ),
+            strings(&["E", "D", "A", "C", "B"])
+        );
+        // ties keep the order in which the records matched
+        assert_eq!(
+            column(&mut executor, "select title from book order by genre desc"),
+            strings(&["C", "D", "A", "B", "E"])
+        );
+        assert_eq!(
+            column(&mut executor, "select title as t from book order by t desc"),
+            strings(&["E", "D", "C", "B", "A"])
+        );
+    }
+
+    #[test]
+    fn limit_and_offset_page_through_the_rows() {
+        let mut executor = library();
+
+        for (clauses, expected) in [
+            ("order by title limit 2", &["A", "B"][..]),
+            ("order by title limit 2 offset 1", &["B", "C"]),
+            ("order by title desc offset 3", &["B", "A"]),
+            ("order by title limit 10 offset 4", &["E"]),
+            ("order by title limit 0", &[]),
+            ("order by title offset 5", &[]),
+            ("limit 2 offset 1", &["B", "C"]),
+            ("offset 4", &["E"]),
+        ] {
+            assert_eq!(
+                column(&mut executor, &format!("select title from book {clauses}")),
+                strings(expected),
+                "{clauses}"
+            );
+        }
+    }
+
+    #[test]
+    fn distinct_keeps_the_first_of_equal_rows() {
+        let mut executor = library();
+
+        assert_eq!(
+            column(&mut executor, "select distinct genre from book"),
+            strings(&["sf", "ya"])
+        );
+        assert_eq!(
+            column(&mut executor, "select distinct genre from book offset 1"),
+            strings(&["ya"])
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select distinct genre from book order by pages desc"
+            ),
+            strings(&["sf", "ya"])
+        );
+        // `d` is the first `ya` book by pages, so `ya` sorts before `sf`, whose first is `e`
+        assert_eq!(
+            column(
+                &mut executor,
+                "select distinct genre from book where pages > 0 order by pages desc"
+            ),
+            strings(&["ya", "sf"])
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select distinct genre from book order by pages limit 1 offset 1"
+            ),
+            strings(&["ya"])
+        );
+    }
+
+    fn failure(executor: &mut Executor, statement: &str) -> ExecutionError {
+        match execute(executor, statement) {
+            Err(error) => error,
+            Ok(outcome) => panic!("`{statement}` did not fail: {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn arithmetic_evaluates_per_row() {
+        let mut executor = library();
+        let float = |value: f64| Some(graph::Value::Float(value));
+
+        assert_eq!(
+            select(
+                &mut executor,
+                "select pages % 7, pages / 7, pages * 2 - 1, -pages, pages + 0.5 from book \
+                 where title = \"A\""
+            ),
+            [[int(6), int(42), int(599), int(-300), float(300.5)]]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select -(pages - 400) from book order by title"
+            ),
+            [int(100), int(280), int(280), int(-50), None]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select 1 + 2 * 3 - -4 from book where title = \"A\""
+            ),
+            [int(11)]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select title + \"!\" from book where pages = 120 order by title"
+            ),
+            strings(&["B!", "C!"])
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select 7 / 2.0 from book where title = \"A\""
+            ),
+            [float(3.5)]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select title from book where pages * 2 > 500 order by title"
+            ),
+            strings(&["A", "D"])
+        );
+    }
+
+    #[test]
+    fn functions_evaluate_their_arguments() {
+        let mut executor = library();
+        run(
+            &mut executor,
+            &["create book:f { title = \"Émile\", pages = -80, tags = [1, 2, 3] }"],
+        );
+
+        assert_eq!(
+            select(
+                &mut executor,
+                "select lower(title), upper(title), len(title), abs(-pages), abs(pages) from book \
+                 where title = \"Émile\""
+            ),
+            [[string("émile"), string("ÉMILE"), int(5), int(80), int(80)]]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select len(tags) from book where tags is not null"
+            ),
+            [int(3)]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select abs(-1.5) from book where title = \"A\""
+            ),
+            [Some(graph::Value::Float(1.5))]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select lower(title) from book where upper(genre) = \"YA\" order by title"
+            ),
+            strings(&["c", "d"])
+        );
+        // a missing argument makes the call missing too
+        assert_eq!(
+            column(
+                &mut executor,
+                "select abs(pages) from book where title = \"E\""
+            ),
+            [None]
+        );
+
+        assert!(matches!(
+            failure(&mut executor, "select lower(pages) from book"),
+            ExecutionError::InvalidOperands(function, types) if function == "lower()" && types == ["int"]
+        ));
+        assert!(matches!(
+            failure(&mut executor, "select reverse(title) from book"),
+            ExecutionError::UnknownFunction(function) if function == "reverse"
+        ));
+        assert!(matches!(
+            failure(&mut executor, "select abs(pages, 1) from book"),
+            ExecutionError::WrongArgumentCount {
+                expected: 1,
+                found: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn properties_compare_with_each_other() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create book:a { title = \"A\", pages = 300, min_pages = 200 }",
+                "create book:b { title = \"B\", pages = 120, min_pages = 200 }",
+                "create book:c { title = \"C\", pages = 200, min_pages = 200.0 }",
+                "create book:d { title = \"D\", pages = 150 }",
+            ],
+        );
+
+        for (filter, expected) in [
+            ("pages > min_pages", &["A"][..]),
+            ("pages >= min_pages", &["A", "C"]),
+            ("pages = min_pages", &["C"]),
+            ("pages != min_pages", &["A", "B"]),
+            ("pages < min_pages + 100", &["B", "C"]),
+        ] {
+            assert_eq!(
+                column(
+                    &mut executor,
+                    &format!("select title from book where {filter} order by title")
+                ),
+                strings(expected),
+                "{filter}"
+            );
+        }
+    }
+
+    #[test]
+    fn integer_arithmetic_fails_on_division_by_zero_and_overflow() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &["create n:x { v = -9223372036854775807, zero = 0 }"],
+        );
+
+        for expression in ["1 / zero", "1 % zero", "v / 0"] {
+            assert!(
+                matches!(
+                    failure(&mut executor, &format!("select {expression} from n")),
+                    ExecutionError::DivisionByZero
+                ),
+                "{expression}"
+            );
+        }
+        for expression in [
+            "(v - 1) / -1",
+            "(v - 1) % -1",
+            "-(v - 1)",
+            "abs(v - 1)",
+            "v - 2",
+            "-v * 2",
+        ] {
+            assert!(
+                matches!(
+                    failure(&mut executor, &format!("select {expression} from n")),
+                    ExecutionError::Overflow
+                ),
+                "{expression}"
+            );
+        }
+        assert_eq!(
+            select(&mut executor, "select v - 1, -v, 1.0 / zero from n"),
+            [[
+                int(i64::MIN),
+                int(i64::MAX),
+                Some(graph::Value::Float(f64::INFINITY))
+            ]]
+        );
+        assert!(matches!(
+            failure(&mut executor, "select -\"a\" from n"),
+            ExecutionError::InvalidOperands(operator, types) if operator == "-" && types == ["string"]
+        ));
+    }
+
+    fn list(values: Vec<Option<graph::Value>>) -> Option<graph::Value> {
+        Some(graph::Value::List(values.into_iter().flatten().collect()))
+    }
+
+    #[test]
+    fn aggregates_summarise_each_group() {
+        let mut executor = library();
+        let float = |value: f64| Some(graph::Value::Float(value));
+
+        assert_eq!(
+            select(
+                &mut executor,
+                "select genre, count(*), count(pages), sum(pages), avg(pages), min(pages), \
+                 max(pages), collect(title), count(distinct pages) from book group by genre \
+                 order by genre"
+            ),
+            [
+                [
+                    string("sf"),
+                    int(3),
+                    int(2),
+                    int(420),
+                    float(210.0),
+                    int(120),
+                    int(300),
+                    list(strings(&["A", "B", "E"])),
+                    int(2)
+                ],
+                [
+                    string("ya"),
+                    int(2),
+                    int(2),
+                    int(570),
+                    float(285.0),
+                    int(120),
+                    int(450),
+                    list(strings(&["C", "D"])),
+                    int(2)
+                ]
+            ]
+        );
+        // without `group by`, all records form one group
+        assert_eq!(
+            select(
+                &mut executor,
+                "select count(*), count(distinct pages), min(title), max(title) from book"
+            ),
+            [[int(5), int(3), string("A"), string("E")]]
+        );
+        assert_eq!(
+            column(
+                &mut executor,
+                "select count(*) from book group by pages order by count(*) desc, pages"
+            ),
+            [int(2), int(1), int(1), int(1)]
+        );
+    }
+
+    #[test]
+    fn aggregates_over_no_records_are_zero_or_missing() {
+        let mut executor = library();
+
+        assert_eq!(
+            select(
+                &mut executor,
+                "select count(*), count(pages), sum(pages), avg(pages), min(pages), max(pages), \
+                 collect(title) from book where pages > 1000"
+            ),
+            [[int(0), int(0), None, None, None, None, list(Vec::new())]]
+        );
+        // with `group by`, no records make no groups
+        assert!(
+            select(
+                &mut executor,
+                "select genre, count(*) from book where pages > 1000 group by genre"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn having_keeps_the_groups_that_match() {
+        let mut executor = library();
+
+        for (having, expected) in [
+            ("count(*) > 2", &["sf"][..]),
+            ("sum(pages) > 500", &["ya"]),
+            ("max(pages) < 1000 and min(pages) = 120", &["sf", "ya"]),
+            ("genre = \"ya\"", &["ya"]),
+            ("avg(pages) > 1000", &[]),
+        ] {
+            assert_eq!(
+                column(
+                    &mut executor,
+                    &format!(
+                        "select genre from book group by genre having {having} order by genre"
+                    )
+                ),
+                strings(expected),
+                "{having}"
+            );
+        }
+    }
+
+    #[test]
+    fn aggregating_paths_groups_by_a_step() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create author:p { name = \"Pratchett\" }",
+                "create author:h { name = \"Herbert\" }",
+                "create book:a { title = \"Mort\" }",
+                "create book:b { title = \"Sourcery\" }",
+                "create book:c { title = \"Eric\" }",
+                "create book:d { title = \"Jingo\" }",
+                "create book:e { title = \"Dune\" }",
+                "create book:f { title = \"Children of Dune\" }",
+                "link author:p -> wrote -> book:a",
+                "link author:p -> wrote -> book:b",
+                "link author:p -> wrote -> book:c",
+                "link author:p -> wrote -> book:d",
+                "link author:h -> wrote -> book:e",
+                "link author:h -> wrote -> book:f",
+            ],
+        );
+
+        assert_eq!(
+            select(
+                &mut executor,
+                "select author.name, count(*) from author->book group by author.name \
+                 having count(*) > 3"
+            ),
+            [[string("Pratchett"), int(4)]]
+        );
+        assert_eq!(
+            select(
+                &mut executor,
+                "select author.name, min(title) from author->book group by author.name \
+                 order by author.name"
+            ),
+            [
+                [string("Herbert"), string("Children of Dune")],
+                [string("Pratchett"), string("Eric")]
+            ]
+        );
+        assert!(matches!(
+            failure(&mut executor, "select author.name, count(*) from author->book"),
+            ExecutionError::UngroupedProperty(property) if property == "author.name"
+        ));
+    }
+
+    #[test]
+    fn aggregated_queries_only_read_grouped_properties() {
+        let mut executor = library();
+
+        for (query, property) in [
+            ("select title, count(*) from book", "title"),
+            ("select genre, title from book group by genre", "title"),
+            (
+                "select genre from book group by genre order by pages",
+                "pages",
+            ),
+            (
+                "select genre from book group by genre having pages > 1",
+                "pages",
+            ),
+            (
+                "select genre, pages + count(*) from book group by genre",
+                "pages",
+            ),
+        ] {
+            assert!(
+                matches!(
+                    failure(&mut executor, query),
+                    ExecutionError::UngroupedProperty(name) if name == property
+                ),
+                "{query}"
+            );
+        }
+        assert!(matches!(
+            failure(&mut executor, "select * from book group by genre"),
+            ExecutionError::GroupedAll
+        ));
+        assert!(matches!(
+            failure(&mut executor, "select title from book where count(*) > 1"),
+            ExecutionError::MisplacedAggregate("`where`")
+        ));
+        assert!(matches!(
+            failure(&mut executor, "select sum(title) from book"),
+            ExecutionError::InvalidOperands(function, types) if function == "sum()" && types == ["string"]
+        ));
+        assert_eq!(
+            select(
+                &mut executor,
+                "select genre, count(*) * 10 from book group by genre order by genre"
+            ),
+            [[string("sf"), int(30)], [string("ya"), int(20)]]
+        );
+    }
+
+    fn selected(executor: &mut Executor, query: &str) -> Rows {
+        match execute(executor, query) {
+            Ok(Outcome::Selected(rows)) => rows,
+            other => panic!("`{query}` did not select: {other:?}"),
+        }
+    }
+
+    fn authors() -> Executor {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create author:h { name = \"Herbert\", meta = { born = 1920 } }",
+                "create author:s { name = \"Shelley\" }",
+                "create book:d { title = \"Dune\", meta = { isbn = \"0441013597\", \
+                 print = { pages = 412 } } }",
+                "create book:f { title = \"Frankenstein\", meta = { isbn = \"0486282112\" } }",
+                "create book:m { title = \"Dune Messiah\", meta = \"none\" }",
+                "link author:h -> wrote -> book:d",
+                "link author:h -> wrote -> book:m",
+                "link author:s -> wrote -> book:f",
+            ],
+        );
+
+        executor
+    }
+
+    #[test]
+    fn fields_are_read_from_the_path_step_they_name() {
+        let mut executor = authors();
+
+        let rows = selected(
+            &mut executor,
+            "select book.title as title, author.name as writer from author->book order by title",
+        );
+        assert_eq!(rows.columns(), ["title", "writer"]);
+        assert_eq!(
+            rows.rows(),
+            [
+                [string("Dune"), string("Herbert")],
+                [string("Dune Messiah"), string("Herbert")],
+                [string("Frankenstein"), string("Shelley")]
+            ]
+        );
+
+        // unaliased fields are named as written, bare properties come from the end of the path
+        let rows = selected(
+            &mut executor,
+            "select author.name, title from author->book where author.name = \"Shelley\"",
+        );
+        assert_eq!(rows.columns(), ["author.name", "title"]);
+        assert_eq!(rows.rows(), [[string("Shelley"), string("Frankenstein")]]);
+
+        assert_eq!(
+            column(
+                &mut executor,
+                "select book.title from author->book where author.name like \"H%\" \
+                 and book.title != \"Dune\""
+            ),
+            strings(&["Dune Messiah"])
+        );
+        assert!(matches!(
+            failure(&mut executor, "select book.title from book->book"),
+            ExecutionError::AmbiguousStep(step) if step == "book"
+        ));
+    }
+
+    #[test]
+    fn dots_read_entries_of_map_properties() {
+        let mut executor = authors();
+
+        let rows = selected(
+            &mut executor,
+            "select title, meta.isbn as isbn, meta.print.pages from book order by title",
+        );
+        assert_eq!(rows.columns(), ["title", "isbn", "meta.print.pages"]);
+        assert_eq!(
+            rows.rows(),
+            [
+                [string("Dune"), string("0441013597"), int(412)],
+                // entries of values that are not maps are missing
+                [string("Dune Messiah"), None, None],
+                [string("Frankenstein"), string("0486282112"), None]
+            ]
+        );
+        assert_eq!(
+            matching(&mut executor, "meta.isbn = \"0486282112\""),
+            strings(&["Frankenstein"])
+        );
+        assert_eq!(
+            matching(&mut executor, "meta.isbn is null"),
+            strings(&["Dune Messiah"])
+        );
+        assert_eq!(
+            matching(&mut executor, "meta.print.pages > 400"),
+            strings(&["Dune"])
+        );
+        // maps of other steps are reached through the step
+        assert_eq!(
+            select(
+                &mut executor,
+                "select author.meta.born, book.meta.isbn from author->book \
+                 where book.title = \"Dune\""
+            ),
+            [[int(1920), string("0441013597")]]
+        );
+    }
+
+    #[test]
+    fn only_rows_within_the_limit_are_projected() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create n:x { v = 0 }",
+                "create n:y { v = 2 }",
+                "create n:z { v = 3 }",
+            ],
+        );
+
+        assert_eq!(
+            column(&mut executor, "select 6 / v from n order by v desc limit 2"),
+            [int(2), int(3)]
+        );
+        assert!(matches!(
+            execute(&mut executor, "select 6 / v from n order by v desc"),
+            Err(ExecutionError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn numbers_that_are_equal_are_one_value_for_distinct_and_group_by() {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create n:a { v = 1 }",
+                "create n:b { v = 1.0 }",
+                "create n:c { v = 2 }",
+                "create n:d { v = -0.0 }",
+                "create n:e { v = 0 }",
+            ],
+        );
+
+        assert_eq!(
+            column(&mut executor, "select count(*) from n where v = 1"),
+            [int(2)]
+        );
+        assert_eq!(
+            column(&mut executor, "select distinct v from n"),
+            [int(1), int(2), Some(graph::Value::Float(-0.0))]
+        );
+        assert_eq!(
+            select(
+                &mut executor,
+                "select v, count(*) from n group by v order by v"
+            ),
+            [
+                [Some(graph::Value::Float(-0.0)), int(2)],
+                [int(1), int(2)],
+                [int(2), int(1)]
+            ]
+        );
+
+        // 2^53 + 1 is the first int that no float holds, so converting it would round it to 2^53
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create n:a { v = 9007199254740993 }",
+                "create n:b { v = 9007199254740992.0 }",
+            ],
+        );
+        for (filter, count) in [
+            ("v = 9007199254740992.0", 1),
+            ("v = 9007199254740993", 1),
+            ("v > 9007199254740992.0", 1),
+            ("v >= 9007199254740992", 2),
+            ("v < 9007199254740994.0", 2),
+        ] {
+            let query = format!("select count(*) from n where {filter}");
+            assert_eq!(column(&mut executor, &query), [int(count)], "{filter}");
+        }
+        assert_eq!(
+            column(&mut executor, "select count(distinct v) from n"),
+            [int(2)]
+        );
+        assert_eq!(column(&mut executor, "select distinct v from n").len(), 2);
+    }
+
+    #[test]
+    fn ints_and_floats_compare_exactly() {
+        let order =
+            |int: i64, float: f64| compare(&graph::Value::Int(int), &graph::Value::Float(float));
+        assert_eq!(order(1, 1.5), Some(Ordering::Less));
+        assert_eq!(order(2, 1.5), Some(Ordering::Greater));
+        assert_eq!(order(-1, -1.5), Some(Ordering::Greater));
+        assert_eq!(order(-2, -1.5), Some(Ordering::Less));
+        assert_eq!(order(3, 3.0), Some(Ordering::Equal));
+        assert_eq!(order(i64::MAX, i64::MAX as f64), Some(Ordering::Less));
+        assert_eq!(order(i64::MIN, i64::MIN as f64), Some(Ordering::Equal));
+        assert_eq!(order(i64::MIN, f64::NEG_INFINITY), Some(Ordering::Greater));
+        assert_eq!(order(0, f64::NAN), None);
+        assert_eq!(
+            compare(&graph::Value::Float(1.5), &graph::Value::Int(1)),
+            Some(Ordering::Greater)
+        );
+    }
+
+    fn shelf() -> Executor {
+        let mut executor = Executor::new(Graph::new());
+        run(
+            &mut executor,
+            &[
+                "create book:a { title = \"Dune\", tags = [\"sf\", \"classic\"] }",
+                "create book:b { title = \"Emma\", tags = [\"romance\"], genre = \"classic\" }",
+                "create book:c { title = \"Anathem\", tags = \"sf\" }",
+                "create book:d { title = \"Nameless\" }",
+                "create book:e { title = \"Numbers\", tags = [1, 2] }",
+            ],
+        );
+
+        executor
+    }
+
+    fn matching(executor: &mut Executor, filter: &str) -> Vec<Option<graph::Value>> {
+        column(
+            executor,
+            &format!("select title from book where {filter} order by title"),
+        )
+    }
+
+    #[test]
+    fn prefix_scans_of_the_unique_index_select_what_full_scans_do() {
+        let books = [
+            "create book:a { title = \"Dune\", pages = 412 }",
+            "create book:b { title = \"Dune Messiah\", pages = 256 }",
+            "create book:c { title = \"Duma Key\", pages = 611 }",
+            "create book:d { title = \"Emma\", pages = 474 }",
+            "create book:e { title = \"100% Dune\" }",
+            "create book:f { title = \"100 Days\" }",
+            "create book:g { title = [\"Dune\", \"Emma\"] }",
+            "create book:h { title = 7 }",
+            "create author:x { title = \"Dune\" }",
+        ];
+        let mut scanned = Executor::new(Graph::new());
+        run(&mut scanned, &books);
+        let mut indexed = Executor::new(Graph::new());
+        run(&mut indexed, &["create constraint unique on book(title)"]);
+        run(&mut indexed, &books);
+        assert!(
+            indexed
+                .graph()
+                .nodes_with_prefix("book", "title", "Du")
+                .is_some()
+        );
+        assert_eq!(
+            matching(&mut indexed, "title like \"100\\%%\""),
+            strings(&["100% Dune"])
+        );
+
+        // inside a transaction, both read the staged graph and its index
+        for transaction in [false, true] {
+            if transaction {
+                let dusk = ["begin", "create book:i { title = \"Dusk\", pages = 300 }"];
+                run(&mut indexed, &dusk);
+                run(&mut scanned, &dusk);
+                assert!(matching(&mut indexed, "title like \"Du%\"").contains(&string("Dusk")));
+            }
+
+            for filter in [
+                "title like \"Du%\"",
+                "title like \"Dune%\"",
+                "title like \"D_ne\"",
+                "title like \"Dune\"",
+                "title like \"Dx%\"",
+                "title like \"100\\%%\"",
+                "title like \"Du%\" and pages > 300",
+                "book.title like \"Du%\"",
+            ] {
+                // the index yields its rows in key order, the full scan in node order
+                let rows = |executor: &mut Executor| {
+                    let mut rows = select(
+                        executor,
+                        &format!("select title, pages from book where {filter}"),
+                    );
+                    rows.sort_by_key(|row| format!("{row:?}"));
+                    rows
+                };
+                assert_eq!(rows(&mut indexed), rows(&mut scanned), "{filter}");
+                assert_eq!(
+                    matching(&mut indexed, filter),
+                    matching(&mut scanned, filter),
+                    "{filter}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn missing_properties_only_satisfy_is_null() {
+        let mut executor = shelf();
+        let all = strings(&["Anathem", "Dune", "Emma", "Nameless", "Numbers"]);
+
+        assert_eq!(
+            matching(&mut executor, "genre = \"classic\""),
+            strings(&["Emma"])
+        );
+        assert_eq!(
+            matching(&mut executor, "genre != \"x\""),
+            strings(&["Emma"])
+        );
+        assert_eq!(matching(&mut executor, "genre in [\"x\"]"), []);
+        assert_eq!(
+            matching(&mut executor, "genre not in [\"x\"]"),
+            strings(&["Emma"])
+        );
+        assert_eq!(matching(&mut executor, "not genre in [\"x\"]"), all);
+        assert_eq!(
+            matching(&mut executor, "genre contains \"\""),
+            strings(&["Emma"])
+        );
+        assert_eq!(
+            matching(&mut executor, "genre starts with \"\""),
+            strings(&["Emma"])
+        );
+        assert_eq!(
+            matching(&mut executor, "genre matches \"\""),
+            strings(&["Emma"])
+        );
+        assert_eq!(
+            matching(&mut executor, "tags is null"),
+            strings(&["Nameless"])
+        );
+        assert_eq!(
+            matching(&mut executor, "tags is not null"),
+            strings(&["Anathem", "Dune", "Emma", "Numbers"])
+        );
+    }
+
+    #[test]
+    fn values_of_another_type_never_compare() {
+        let mut executor = shelf();
+
+        assert_eq!(
+            matching(&mut executor, "tags = \"sf\""),
+            strings(&["Anathem"])
+        );
+        assert_eq!(matching(&mut executor, "tags != \"sf\""), []);
+        assert_eq!(
+            matching(&mut executor, "tags like \"%\""),
+            strings(&["Anathem", "Dune", "Emma"])
+        );
+        assert_eq!(
+            matching(&mut executor, "title in [1, \"Dune\"]"),
+            strings(&["Dune"])
+        );
+        assert_eq!(
+            matching(&mut executor, "title not in [1, \"Dune\"]"),
+            strings(&["Anathem", "Emma", "Nameless", "Numbers"])
+        );
+    }
+
+    #[test]
+    fn list_properties_match_when_any_element_does() {
+        let mut executor = shelf();
+
+        for (filter, expected) in [
+            ("tags contains \"sf\"", &["Anathem", "Dune"][..]),
+            ("tags contains \"f\"", &["Anathem"]),
+            ("tags contains 1.0", &["Numbers"]),
+            ("tags like \"cl%\"", &["Dune"]),
+            ("tags ilike \"ROM%\"", &["Emma"]),
+            ("tags starts with \"rom\"", &["Emma"]),
+            ("tags ends with \"f\"", &["Anathem", "Dune"]),
+            ("tags matches \"^s\"", &["Anathem", "Dune"]),
+            ("tags matches \"ss\"", &["Dune"]),
+            ("tags = [1, 2.0]", &["Numbers"]),
+            ("tags in [[1, 2], \"sf\"]", &["Anathem", "Numbers"]),
+        ] {
+            assert_eq!(
+                matching(&mut executor, filter),
+                strings(expected),
+                "{filter}"
+            );
+        }
+    }
+
+    #[test]
+    fn in_needs_a_list_and_matches_a_valid_pattern() {
+        let mut executor = shelf();
+
+        assert!(matches!(
+            execute(&mut executor, "select * from book where title in \"Dune\""),
+            Err(ExecutionError::InvalidOperands(operator, _)) if operator == "in"
+        ));
+        assert!(matches!(
+            execute(
+                &mut executor,
+                "select * from book where title matches \"(\""
+            ),
+            Err(ExecutionError::InvalidPattern(..))
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::mql::lexer::{Lexer, Span, Token};
+use crate::mql::parser::{ParseError, Parser};
+use crate::mql::script::Script;
+
+/// Rewrites `script` in canonical form: every statement as its [`AST`](crate::mql::parser::AST)
+/// prints, on a line of its own and ended by `;`.
+///
+/// Comments are kept. Those on a line of their own stay there, those after a statement on its
+/// line stay after it, and those inside a statement move to the lines before it. Blank lines
+/// between statements and comments are kept, but never more than one in a row.
+///
+/// Fails with the errors of every statement that does not parse.
+pub fn format(script: &Script) -> Result<String, Vec<ParseError>> {
+    let source = script.source();
+    let (statements, errors) = Parser::new(Lexer::new(source)).parse_recovering();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let comments = Lexer::new(source).filter_map(|(token, span)| match token {
+        Token::Comment(comment) => Some((comment, span)),
+        _ => None,
+    });
+
+    // comments inside a statement are placed at its start, ahead of it
+    let mut items = comments
+        .map(|(comment, span)| {
+            let inside = statements.iter().find(|(_, statement)| {
+                statement.start() < span.start() && span.end() <= statement.end()
+            });
+            match inside {
+                Some((_, statement)) => Item {
+                    text: comment,
+                    span: Span::new(statement.start(), statement.start()),
+                    trailing: false,
+                },
+                None => Item {
+                    text: comment,
+                    span,
+                    trailing: true,
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    for (ast, span) in statements {
+        items.push(Item {
+            text: format!("{ast};"),
+            span,
+            trailing: false,
+        });
+    }
+    // the sort is stable, so comments moved out of a statement keep their order, ahead of it
+    items.sort_by_key(|item| item.span.start());
+
+    let mut output = String::new();
+    let mut end = None;
+    for item in items {
+        if let Some(end) = end {
+            let gap = source.get(end..item.span.start()).unwrap_or_default();
+            if item.trailing && !gap.contains('\n') {
+                output.push(' ');
+            } else if gap.matches('\n').count() > 1 {
+                output.push_str("\n\n");
+            } else {
+                output.push('\n');
+            }
+        }
+        output.push_str(&item.text);
+        end = Some(item.span.end());
+    }
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Statement or comment of the formatted script.
+struct Item {
+    text: String,
+    /// Where the item is in the script, which for a comment moved out of a statement is the
+    /// start of the statement.
+    span: Span,
+    /// Whether the item stays on the line of the one before it if it was there in the script.
+    trailing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(source: &str) -> Result<String, Vec<ParseError>> {
+        format(&Script::new(source))
+    }
+
+    #[test]
+    fn statements_get_a_line_each_and_comments_stay() {
+        let source =
+            "-- books\nSELECT * FROM book;   select  * /* inside */ from a; -- after\n\n\nbegin";
+
+        assert_eq!(
+            formatted(source).unwrap(),
+            "-- books\nselect * from book;\n/* inside */\nselect * from a; -- after\n\nbegin;\n"
+        );
+    }
+
+    #[test]
+    fn every_statement_that_does_not_parse_is_reported() {
+        let source = "select * from a;\nselect from b;\ncreate a:x { };\nlink a:x -> ;";
+        let errors = formatted(source).unwrap_err();
+
+        let found = errors
+            .iter()
+            .map(|error| &source[error.span.start()..error.span.end()])
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["from", ";"]);
+        assert_eq!(errors[1].span.start(), source.len() - 1);
+    }
+}
@@ -1,8 +1,10 @@
+use std::fmt;
 use std::iter::Peekable;
 use std::str::Chars;
 
-#[derive(Debug, Clone)]
-pub(crate) struct Span(usize, usize);
+/// Byte range `start..end` of a token in the lexed source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span(usize, usize);
 
 impl Span {
     pub fn new(start: usize, end: usize) -> Self {
@@ -20,10 +22,15 @@ impl Span {
     pub fn end(&self) -> usize {
         self.1
     }
+
+    /// The same range in a source where the lexed text starts at byte `offset`.
+    pub fn offset(&self, offset: usize) -> Self {
+        Self(self.0 + offset, self.1 + offset)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum Token {
+pub enum Token {
     Colon,
     RBracket,
     LBracket,
@@ -35,6 +42,10 @@ pub(crate) enum Token {
     Comma,
     Semicolon,
     Asterisk,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
     ArrowRight,
     ArrowLeft,
     GreaterThan,
@@ -44,11 +55,21 @@ pub(crate) enum Token {
     GreaterThanOrEquals,
     SmallerThanOrEquals,
     Identifier(String),
+    /// Identifier written in backticks, such as `` `first name` ``, which is never a keyword.
+    QuotedIdentifier(String),
     StringLiteral(String),
     IntLiteral(usize),
     FloatLiteral(f64),
+    BoolLiteral(bool),
+    Null,
+    /// Placeholder `$name` for a value given when a prepared statement runs.
+    Parameter(String),
     Select,
     Create,
+    Define,
+    Begin,
+    Commit,
+    Rollback,
     Where,
     Like,
     Link,
@@ -56,7 +77,13 @@ pub(crate) enum Token {
     And,
     Or,
     Not,
+    /// `-- text` up to the end of the line or `/* text */`, as written. Block comments nest.
+    Comment(String),
     Unknown(char),
+    /// Number literal that does not convert to a number.
+    InvalidNumber(String),
+    /// Opening of a comment or quoted identifier, named here, that the input ends inside of.
+    Unterminated(&'static str),
 }
 
 impl Token {
@@ -73,6 +100,10 @@ impl Token {
             Token::Comma => TokenKind::Comma,
             Token::Semicolon => TokenKind::Semicolon,
             Token::Asterisk => TokenKind::Asterisk,
+            Token::Plus => TokenKind::Plus,
+            Token::Minus => TokenKind::Minus,
+            Token::Slash => TokenKind::Slash,
+            Token::Percent => TokenKind::Percent,
             Token::ArrowRight => TokenKind::ArrowRight,
             Token::ArrowLeft => TokenKind::ArrowLeft,
             Token::GreaterThan => TokenKind::GreaterThan,
@@ -81,12 +112,19 @@ impl Token {
             Token::NotEquals => TokenKind::NotEquals,
             Token::GreaterThanOrEquals => TokenKind::GreaterThanOrEquals,
             Token::SmallerThanOrEquals => TokenKind::SmallerThanOrEquals,
-            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Identifier(_) | Token::QuotedIdentifier(_) => TokenKind::Identifier,
             Token::StringLiteral(_) => TokenKind::StringLiteral,
             Token::IntLiteral(_) => TokenKind::IntLiteral,
             Token::FloatLiteral(_) => TokenKind::FloatLiteral,
+            Token::BoolLiteral(_) => TokenKind::BoolLiteral,
+            Token::Null => TokenKind::Null,
+            Token::Parameter(_) => TokenKind::Parameter,
             Token::Select => TokenKind::Select,
             Token::Create => TokenKind::Create,
+            Token::Define => TokenKind::Define,
+            Token::Begin => TokenKind::Begin,
+            Token::Commit => TokenKind::Commit,
+            Token::Rollback => TokenKind::Rollback,
             Token::Where => TokenKind::Where,
             Token::Like => TokenKind::Like,
             Token::Link => TokenKind::Link,
@@ -94,13 +132,21 @@ impl Token {
             Token::And => TokenKind::And,
             Token::Or => TokenKind::Or,
             Token::Not => TokenKind::Not,
-            Token::Unknown(_) => TokenKind::Unknown,
+            Token::Comment(_) => TokenKind::Comment,
+            Token::Unknown(_) | Token::InvalidNumber(_) | Token::Unterminated(_) => {
+                TokenKind::Unknown
+            }
         }
     }
+
+    /// Whether the token is a comment, which the parser skips but a formatter keeps.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Token::Comment(_))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum TokenKind {
+pub enum TokenKind {
     Colon,
     RBracket,
     LBracket,
@@ -112,6 +158,10 @@ pub(crate) enum TokenKind {
     Comma,
     Semicolon,
     Asterisk,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
     ArrowRight,
     ArrowLeft,
     GreaterThan,
@@ -124,8 +174,15 @@ pub(crate) enum TokenKind {
     StringLiteral,
     IntLiteral,
     FloatLiteral,
+    BoolLiteral,
+    Null,
+    Parameter,
     Select,
     Create,
+    Define,
+    Begin,
+    Commit,
+    Rollback,
     Where,
     Like,
     Link,
@@ -133,11 +190,110 @@ pub(crate) enum TokenKind {
     And,
     Or,
     Not,
+    Comment,
     Unknown,
 }
 
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(identifier) | Token::QuotedIdentifier(identifier) => {
+                write!(f, "identifier `{identifier}`")
+            }
+            Token::StringLiteral(string) => write!(f, "string {string:?}"),
+            Token::IntLiteral(int) => write!(f, "`{int}`"),
+            Token::FloatLiteral(float) => write!(f, "`{float}`"),
+            Token::BoolLiteral(bool) => write!(f, "`{bool}`"),
+            Token::Parameter(name) => write!(f, "parameter `${name}`"),
+            Token::Unknown(c) => write!(f, "`{c}`"),
+            Token::InvalidNumber(number) => write!(f, "`{number}`"),
+            Token::Unterminated(what) => write!(f, "unterminated {what}"),
+            token => write!(f, "{}", token.kind()),
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenKind::Colon => "`:`",
+            TokenKind::RBracket => "`]`",
+            TokenKind::LBracket => "`[`",
+            TokenKind::RParen => "`)`",
+            TokenKind::LParen => "`(`",
+            TokenKind::RBrace => "`}`",
+            TokenKind::LBrace => "`{`",
+            TokenKind::Dot => "`.`",
+            TokenKind::Comma => "`,`",
+            TokenKind::Semicolon => "`;`",
+            TokenKind::Asterisk => "`*`",
+            TokenKind::Plus => "`+`",
+            TokenKind::Minus => "`-`",
+            TokenKind::Slash => "`/`",
+            TokenKind::Percent => "`%`",
+            TokenKind::ArrowRight => "`->`",
+            TokenKind::ArrowLeft => "`<-`",
+            TokenKind::GreaterThan => "`>`",
+            TokenKind::SmallerThan => "`<`",
+            TokenKind::Equals => "`=`",
+            TokenKind::NotEquals => "`!=`",
+            TokenKind::GreaterThanOrEquals => "`>=`",
+            TokenKind::SmallerThanOrEquals => "`<=`",
+            TokenKind::Identifier => "identifier",
+            TokenKind::StringLiteral => "string",
+            TokenKind::IntLiteral => "integer",
+            TokenKind::FloatLiteral => "float",
+            TokenKind::BoolLiteral => "boolean",
+            TokenKind::Null => "`null`",
+            TokenKind::Parameter => "parameter",
+            TokenKind::Select => "`select`",
+            TokenKind::Create => "`create`",
+            TokenKind::Define => "`define`",
+            TokenKind::Begin => "`begin`",
+            TokenKind::Commit => "`commit`",
+            TokenKind::Rollback => "`rollback`",
+            TokenKind::Where => "`where`",
+            TokenKind::Like => "`like`",
+            TokenKind::Link => "`link`",
+            TokenKind::From => "`from`",
+            TokenKind::And => "`and`",
+            TokenKind::Or => "`or`",
+            TokenKind::Not => "`not`",
+            TokenKind::Comment => "comment",
+            TokenKind::Unknown => "unknown character",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Token of the keyword `word`, or `None` if it is not one. Keywords are case-insensitive.
+pub(crate) fn keyword(word: &str) -> Option<Token> {
+    let token = match word.to_ascii_lowercase().as_str() {
+        "select" => Token::Select,
+        "where" => Token::Where,
+        "create" => Token::Create,
+        "define" => Token::Define,
+        "begin" => Token::Begin,
+        "commit" => Token::Commit,
+        "rollback" => Token::Rollback,
+        "like" => Token::Like,
+        "not" => Token::Not,
+        "link" => Token::Link,
+        "from" => Token::From,
+        "true" => Token::BoolLiteral(true),
+        "false" => Token::BoolLiteral(false),
+        "null" => Token::Null,
+        "and" => Token::And,
+        "or" => Token::Or,
+        _ => return None,
+    };
+
+    Some(token)
+}
+
 #[derive(Debug, Clone)]
-pub(crate) struct Lexer<'c> {
+pub struct Lexer<'c> {
     input: Peekable<Chars<'c>>,
     position: usize,
 }
@@ -150,6 +306,7 @@ impl<'c> Lexer<'c> {
         }
     }
 
+    #[allow(clippy::redundant_pattern_matching)]
     fn next_token(&mut self) -> Option<(Token, Span)> {
         let current_position = self.position;
         match self.next_char()? {
@@ -158,12 +315,64 @@ impl<'c> Lexer<'c> {
             ')' => self.emit_token(current_position, Token::RParen),
             '[' => self.emit_token(current_position, Token::LBracket),
             ']' => self.emit_token(current_position, Token::RBracket),
-            '{' => self.emit_token(current_position, Token::RBrace),
-            '}' => self.emit_token(current_position, Token::LBrace),
+            '{' => self.emit_token(current_position, Token::LBrace),
+            '}' => self.emit_token(current_position, Token::RBrace),
             '.' => self.emit_token(current_position, Token::Dot),
             ',' => self.emit_token(current_position, Token::Comma),
             '*' => self.emit_token(current_position, Token::Asterisk),
+            '+' => self.emit_token(current_position, Token::Plus),
+            '/' => {
+                if self.next_char_if(|c| c == '*').is_none() {
+                    return self.emit_token(current_position, Token::Slash);
+                }
+
+                let mut comment = String::from("/*");
+                let mut depth = 1;
+                while let Some(c) = self.next_char() {
+                    comment.push(c);
+                    if c == '/' && self.peek_nth(0) == Some('*') {
+                        comment.extend(self.next_char());
+                        depth += 1;
+                    } else if c == '*' && self.peek_nth(0) == Some('/') {
+                        comment.extend(self.next_char());
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                if depth > 0 {
+                    return Some((
+                        Token::Unterminated("comment"),
+                        Span::new(current_position, current_position + 2),
+                    ));
+                }
+                self.emit_token(current_position, Token::Comment(comment))
+            }
+            '%' => self.emit_token(current_position, Token::Percent),
             ';' => self.emit_token(current_position, Token::Semicolon),
+            '`' => {
+                let mut identifier = String::new();
+                let mut closed = false;
+
+                // a backtick inside the identifier is written twice
+                while let Some(c) = self.next_char() {
+                    match c {
+                        '`' if self.next_char_if(|c| c == '`').is_none() => {
+                            closed = true;
+                            break;
+                        }
+                        c => identifier.push(c),
+                    }
+                }
+
+                if !closed {
+                    let span = Span::new(current_position, current_position + 1);
+                    return Some((Token::Unterminated("quoted identifier"), span));
+                }
+                self.emit_token(current_position, Token::QuotedIdentifier(identifier))
+            }
             '"' => {
                 let mut string = String::new();
 
@@ -178,7 +387,8 @@ impl<'c> Lexer<'c> {
                                     'r' => string.push('\r'),
                                     '\\' => string.push('\\'),
                                     '"' => string.push('"'),
-                                    _ => string.push(c),
+                                    // kept whole, so that `like "100\%"` escapes the `%`
+                                    _ => string.extend(['\\', c]),
                                 }
                             }
                         }
@@ -190,10 +400,22 @@ impl<'c> Lexer<'c> {
             }
             '-' => {
                 if let Some(_) = self.next_char_if(|c| c == '>') {
-                    return self.emit_token(current_position, Token::ArrowRight)
+                    return self.emit_token(current_position, Token::ArrowRight);
+                }
+                if self.next_char_if(|c| c == '-').is_some() {
+                    let mut comment = String::from("--");
+                    // the line ending, `\n` or `\r\n`, is not part of the comment
+                    while let Some(c) = self.peek_nth(0)
+                        && c != '\n'
+                        && !(c == '\r' && self.peek_nth(1) == Some('\n'))
+                    {
+                        comment.extend(self.next_char());
+                    }
+
+                    return self.emit_token(current_position, Token::Comment(comment));
                 }
 
-                self.emit_token(current_position, Token::Unknown('-'))
+                self.emit_token(current_position, Token::Minus)
             }
             '<' => {
                 if let Some(_) = self.next_char_if(|c| c == '=') {
@@ -223,30 +445,32 @@ impl<'c> Lexer<'c> {
                     identifier.push(c);
                 }
 
-                match identifier.as_str() {
-                    "select" => self.emit_token(current_position, Token::Select),
-                    "where" => self.emit_token(current_position, Token::Where),
-                    "create" => self.emit_token(current_position, Token::Create),
-                    "like" => self.emit_token(current_position, Token::Like),
-                    "link" => self.emit_token(current_position, Token::Link),
-                    "from" => self.emit_token(current_position, Token::From),
-                    "and" | "&&" => self.emit_token(current_position, Token::And),
-                    "or" | "||" => self.emit_token(current_position, Token::Or),
-                    _ => self.emit_token(current_position, Token::Identifier(identifier)),
+                let token = keyword(&identifier).unwrap_or(Token::Identifier(identifier));
+                self.emit_token(current_position, token)
+            }
+            '$' if self
+                .peek_nth(0)
+                .is_some_and(|c| c.is_alphabetic() || c == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(c) = self.next_char_if(|c| c.is_alphanumeric() || c == '_') {
+                    name.push(c);
                 }
+
+                self.emit_token(current_position, Token::Parameter(name))
             }
-            c if c.is_numeric() => {
+            c if c.is_ascii_digit() => {
                 let mut string = String::from(c);
-                while let Some(c) = self.next_char_if(|c| c.is_numeric()) {
+                while let Some(c) = self.next_char_if(|c| c.is_ascii_digit()) {
                     string.push(c);
                 }
 
                 let is_float = if self.peek_nth(0) == Some('.') {
-                    if self.peek_nth(1).map_or(false, |c| c.is_numeric()) {
+                    if self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
                         string.push('.');
                         self.next_char();
 
-                        while let Some(c) = self.next_char_if(|c| c.is_numeric()) {
+                        while let Some(c) = self.next_char_if(|c| c.is_ascii_digit()) {
                             string.push(c);
                         }
 
@@ -258,23 +482,32 @@ impl<'c> Lexer<'c> {
                     false
                 };
 
-                if is_float {
-                    self.emit_token(
-                        current_position,
-                        Token::FloatLiteral(
-                            string
-                                .parse::<f64>()
-                                .expect("This will pass due to previous parsing rules"),
-                        ),
-                    )
+                // an exponent like `e5`, `E-3` or `e+10` only counts when digits follow it
+                let has_exponent = matches!(self.peek_nth(0), Some('e' | 'E'))
+                    && match self.peek_nth(1) {
+                        Some('+' | '-') => self.peek_nth(2).is_some_and(|c| c.is_ascii_digit()),
+                        c => c.is_some_and(|c| c.is_ascii_digit()),
+                    };
+                if has_exponent {
+                    string.extend(self.next_char());
+                    string.extend(self.next_char_if(|c| c == '+' || c == '-'));
+                    while let Some(c) = self.next_char_if(|c| c.is_ascii_digit()) {
+                        string.push(c);
+                    }
+                }
+
+                if is_float || has_exponent {
+                    // too large a float reads as infinity, so this only fails on malformed text
+                    let token = match string.parse::<f64>() {
+                        Ok(float) => Token::FloatLiteral(float),
+                        Err(_) => Token::InvalidNumber(string),
+                    };
+                    self.emit_token(current_position, token)
                 } else {
                     self.emit_token(
                         current_position,
-                        Token::IntLiteral(
-                            string
-                                .parse::<usize>()
-                                .expect("This will pass due to previous parsing rules"),
-                        ),
+                        // too large for any int, which the parser reports as out of range
+                        Token::IntLiteral(string.parse::<usize>().unwrap_or(usize::MAX)),
                     )
                 }
             }
@@ -312,3 +545,150 @@ impl Iterator for Lexer<'_> {
         self.next_token()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mql::parser::{ParseErrorKind, Parser};
+
+    fn tokens(source: &str) -> Vec<Token> {
+        Lexer::new(source)
+            .map(|(token, _)| token)
+            .filter(|token| !token.is_trivia())
+            .collect()
+    }
+
+    #[test]
+    fn numbers_are_made_of_ascii_digits() {
+        assert_eq!(tokens("1²"), [Token::IntLiteral(1), Token::Unknown('²')]);
+        assert_eq!(
+            tokens("1.²"),
+            [Token::IntLiteral(1), Token::Dot, Token::Unknown('²')]
+        );
+        assert_eq!(
+            tokens("1.5²"),
+            [Token::FloatLiteral(1.5), Token::Unknown('²')]
+        );
+        assert_eq!(tokens("٣"), [Token::Unknown('٣')]);
+        assert_eq!(
+            Lexer::new("12²").collect::<Vec<_>>(),
+            [
+                (Token::IntLiteral(12), Span::new(0, 2)),
+                (Token::Unknown('²'), Span::new(2, 4))
+            ]
+        );
+    }
+
+    #[test]
+    fn floats_take_fractions_and_exponents() {
+        assert_eq!(
+            tokens("1.5 2e3 1E-2 4e+1 0.25e2"),
+            [
+                Token::FloatLiteral(1.5),
+                Token::FloatLiteral(2000.0),
+                Token::FloatLiteral(0.01),
+                Token::FloatLiteral(40.0),
+                Token::FloatLiteral(25.0),
+            ]
+        );
+        // without digits after them, `.` and `e` are tokens of their own
+        let x = Token::Identifier("x".to_string());
+        assert_eq!(tokens("3.x"), [Token::IntLiteral(3), Token::Dot, x]);
+        assert_eq!(
+            tokens("7e"),
+            [Token::IntLiteral(7), Token::Identifier("e".to_string())]
+        );
+        assert_eq!(tokens("1e999"), [Token::FloatLiteral(f64::INFINITY)]);
+    }
+
+    #[test]
+    fn ints_too_large_for_any_int_saturate() {
+        assert_eq!(
+            tokens("18446744073709551616"),
+            [Token::IntLiteral(usize::MAX)]
+        );
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        let comment = |text: &str| Token::Comment(text.to_string());
+        let tokens = |source| {
+            Lexer::new(source)
+                .map(|(token, _)| token)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tokens("/* a /* b */ c */ 1"),
+            [comment("/* a /* b */ c */"), Token::IntLiteral(1)]
+        );
+        assert_eq!(tokens("/**/ /*/ */"), [comment("/**/"), comment("/*/ */")]);
+    }
+
+    #[test]
+    fn comments_the_input_ends_inside_of_are_errors() {
+        for source in [
+            "/* to do",
+            "/* fixtures for src/*.mql */ select",
+            "/* a */ /*",
+        ] {
+            let (token, span) = Lexer::new(source).last().unwrap();
+            assert_eq!(token, Token::Unterminated("comment"), "{source}");
+            assert_eq!(&source[span.start()..span.end()], "/*", "{source}");
+        }
+
+        let source = "create a:x { };\n/* fixtures for src/*.mql */\ncreate a:y { };";
+        let error = Parser::parse_one(source).unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnexpectedToken(Token::Unterminated("comment"))
+        );
+        assert_eq!(error.message, "unterminated comment");
+        assert_eq!(error.span, Span::new(16, 18));
+    }
+
+    #[test]
+    fn quoted_identifiers_the_input_ends_inside_of_are_errors() {
+        assert_eq!(
+            tokens("`a``b`"),
+            [Token::QuotedIdentifier("a`b".to_string())]
+        );
+        assert_eq!(
+            Lexer::new("from `un").collect::<Vec<_>>(),
+            [
+                (Token::From, Span::new(0, 4)),
+                (Token::Unterminated("quoted identifier"), Span::new(5, 6))
+            ]
+        );
+        assert_eq!(tokens("`a``"), [Token::Unterminated("quoted identifier")]);
+
+        let error = Parser::parse_one("select * from `un").unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnexpectedToken(Token::Unterminated("quoted identifier"))
+        );
+        assert_eq!(error.message, "unterminated quoted identifier");
+        assert_eq!(error.span, Span::new(14, 15));
+    }
+
+    #[test]
+    fn strings_keep_the_backslash_of_unknown_escapes() {
+        let string = |text: &str| [Token::StringLiteral(text.to_string())];
+        assert_eq!(tokens(r#""a\nb\t\"c\"""#), string("a\nb\t\"c\""));
+        assert_eq!(tokens(r#""a\\b""#), string(r"a\b"));
+        assert_eq!(tokens(r#""100\%""#), string(r"100\%"));
+        assert_eq!(tokens(r#""a\_b\d""#), string(r"a\_b\d"));
+    }
+
+    #[test]
+    fn stray_characters_after_numbers_are_parse_errors() {
+        for source in [
+            "select * from a where x = 1.²;",
+            "select * from a where x = 1²;",
+        ] {
+            let error = Parser::parse_one(source).unwrap_err();
+            let unknown = ParseErrorKind::UnexpectedToken(Token::Unknown('²'));
+            assert_eq!(error.kind, unknown, "{source}");
+            assert_eq!(&source[error.span.start()..error.span.end()], "²");
+        }
+    }
+}
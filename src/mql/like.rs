@@ -0,0 +1,161 @@
+/// Compiled pattern of `like` and `ilike`.
+///
+/// A pattern matches whole strings: `%` stands for any text, including none, `_` for exactly one
+/// character, and `\` makes the character after it literal, so `\%`, `\_` and `\\` match
+/// themselves. Every other character matches itself.
+///
+/// MQL string literals keep the `\` of escapes they do not know, so `"100\%"` is the pattern
+/// `100\%`, while a literal `\` is matched by `"\\\\"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LikePattern {
+    parts: Vec<Part>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Literal(char),
+    One,
+    Any,
+}
+
+impl LikePattern {
+    /// Compiles `pattern`. With `ignore_case`, both the pattern and the matched strings are
+    /// compared in lowercase.
+    pub fn new(pattern: &str, ignore_case: bool) -> Self {
+        let pattern = match ignore_case {
+            true => pattern.to_lowercase(),
+            false => pattern.to_string(),
+        };
+
+        let mut parts = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(char) = chars.next() {
+            parts.push(match char {
+                '%' => Part::Any,
+                '_' => Part::One,
+                // a trailing `\` has nothing to escape and stands for itself
+                '\\' => Part::Literal(chars.next().unwrap_or('\\')),
+                char => Part::Literal(char),
+            });
+        }
+
+        Self { parts, ignore_case }
+    }
+
+    /// Literal text that every string matching the pattern starts with, as written.
+    pub fn prefix(&self) -> String {
+        self.parts
+            .iter()
+            .map_while(|part| match part {
+                Part::Literal(char) => Some(*char),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text = match self.ignore_case {
+            true => text.to_lowercase().chars().collect::<Vec<_>>(),
+            false => text.chars().collect(),
+        };
+
+        // on a mismatch, the last `%` seen takes one more character and matching resumes after it
+        let (mut part, mut position) = (0, 0);
+        let mut backtrack = None;
+        while position < text.len() {
+            match self.parts.get(part) {
+                Some(Part::Any) => {
+                    backtrack = Some((part, position));
+                    part += 1;
+                }
+                Some(Part::One) => {
+                    part += 1;
+                    position += 1;
+                }
+                Some(Part::Literal(char)) if *char == text[position] => {
+                    part += 1;
+                    position += 1;
+                }
+                _ => match backtrack {
+                    Some((any, start)) => {
+                        backtrack = Some((any, start + 1));
+                        part = any + 1;
+                        position = start + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        self.parts[part..].iter().all(|part| *part == Part::Any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        LikePattern::new(pattern, false).is_match(text)
+    }
+
+    #[test]
+    fn percent_matches_any_text_and_underscore_one_character() {
+        assert!(matches("Du%", "Dune"));
+        assert!(matches("Du%", "Du"));
+        assert!(matches("%ne", "Dune"));
+        assert!(matches("%", ""));
+        assert!(!matches("Du%", "Adu"));
+        assert!(matches("D_ne", "Dune"));
+        assert!(matches("D_ne", "Dóne"));
+        assert!(!matches("D_ne", "Dne"));
+        assert!(!matches("D_ne", "Duune"));
+        assert!(!matches("Dune", "Dune Messiah"));
+    }
+
+    #[test]
+    fn escaped_wildcards_match_themselves() {
+        assert!(matches(r"100\%", "100%"));
+        assert!(!matches(r"100\%", "1000"));
+        assert!(matches(r"a\_b", "a_b"));
+        assert!(!matches(r"a\_b", "axb"));
+        assert!(matches(r"a\\b", r"a\b"));
+        assert!(matches(r"\d", "d"));
+    }
+
+    #[test]
+    fn trailing_backslash_stands_for_itself() {
+        assert!(matches(r"a\", r"a\"));
+        assert!(!matches(r"a\", "a"));
+        assert!(matches(r"%\", r"dir\"));
+    }
+
+    #[test]
+    fn ignoring_case_folds_pattern_and_text() {
+        let pattern = LikePattern::new("DU%", true);
+        assert!(pattern.is_match("dune"));
+        assert!(pattern.is_match("DUNE"));
+        assert!(!LikePattern::new("DU%", false).is_match("dune"));
+        assert!(LikePattern::new("ÉTÉ_", true).is_match("été!"));
+    }
+
+    #[test]
+    fn percent_backtracks_to_find_a_later_match() {
+        assert!(matches("%a%b", "aab"));
+        assert!(matches("%a%b", "ab"));
+        assert!(matches("%ab", "aab"));
+        assert!(matches("%a_c", "abcabc"));
+        assert!(!matches("%a%b", "ba"));
+        assert!(!matches("%a%b", "aabc"));
+    }
+
+    #[test]
+    fn prefix_is_the_unescaped_text_before_the_first_wildcard() {
+        assert_eq!(LikePattern::new("Du%ne", false).prefix(), "Du");
+        assert_eq!(LikePattern::new("D_ne", false).prefix(), "D");
+        assert_eq!(LikePattern::new(r"100\%%", false).prefix(), "100%");
+        assert_eq!(LikePattern::new("Dune", false).prefix(), "Dune");
+        assert_eq!(LikePattern::new("%ne", false).prefix(), "");
+    }
+}
@@ -1,2 +1,9 @@
-pub(crate) mod lexer;
-pub(crate) mod parser;
+pub mod diagnostic;
+pub mod executor;
+pub mod format;
+pub mod lexer;
+pub mod like;
+pub mod parser;
+pub mod prepared;
+pub mod rows;
+pub mod script;
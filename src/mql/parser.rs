@@ -1,53 +1,237 @@
+use crate::graph::{self, Constraint, PropertyDefinition, PropertyType};
+use crate::mql::diagnostic::Diagnostic;
+use crate::mql::lexer::{self, Lexer, Span, Token, TokenKind};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
 use std::iter::Peekable;
-use std::collections::HashMap;
-use crate::mql::lexer::{Lexer, Token, TokenKind};
 
-#[derive(Debug)]
-pub(crate) enum ParseError {
+/// What went wrong in a [`ParseError`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
     UnexpectedEOF,
     UnexpectedToken(Token),
     InvalidProjection,
+    /// An integer literal that does not fit into an int.
+    IntOutOfRange,
 }
 
-#[derive(Debug)]
-pub(crate) enum AST {
-    // TODO: insert actual type for filter expression
-    Select(Projection, PathExpression, Option<FilterExpression>),
+/// Failure to parse a statement, pointing at the offending token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// Span of the unexpected token, or an empty span at the end of the input.
+    pub span: Span,
+    /// Kinds of token that would have been accepted instead, if any.
+    pub expected: Vec<TokenKind>,
+    pub message: String,
+    /// Short note shown on the span itself, such as what was expected there.
+    pub label: String,
+}
+
+impl ParseError {
+    fn unexpected(token: Token, span: Span, expected: &[TokenKind]) -> Self {
+        let (message, label) = match expected {
+            // nothing is expected to close it, so what else would fit does not help
+            _ if matches!(token, Token::Unterminated(_)) => {
+                (token.to_string(), "never closed".to_string())
+            }
+            [] => (format!("unexpected {token}"), String::new()),
+            expected => {
+                let expected = describe(expected);
+                (
+                    format!("expected {expected}, found {token}"),
+                    format!("expected {expected}"),
+                )
+            }
+        };
+
+        Self {
+            kind: ParseErrorKind::UnexpectedToken(token),
+            span,
+            expected: expected.to_vec(),
+            message,
+            label,
+        }
+    }
+
+    fn eof(span: Span, expected: &[TokenKind]) -> Self {
+        let message = match expected {
+            [] => "unexpected end of input".to_string(),
+            expected => format!("expected {}, found end of input", describe(expected)),
+        };
+
+        Self {
+            kind: ParseErrorKind::UnexpectedEOF,
+            span,
+            expected: expected.to_vec(),
+            message,
+            label: "input ends here".to_string(),
+        }
+    }
+
+    /// Replaces what the error says was expected, for places that expect something more specific
+    /// than a token kind.
+    fn expecting(mut self, expected: &str) -> Self {
+        let found = match &self.kind {
+            ParseErrorKind::UnexpectedToken(Token::Unterminated(_)) => return self,
+            ParseErrorKind::UnexpectedEOF => "end of input".to_string(),
+            ParseErrorKind::UnexpectedToken(token) => {
+                self.label = format!("expected {expected}");
+                token.to_string()
+            }
+            ParseErrorKind::InvalidProjection | ParseErrorKind::IntOutOfRange => {
+                unreachable!("only built with their own message")
+            }
+        };
+        self.message = format!("expected {expected}, found {found}");
+
+        self
+    }
+
+    fn int_out_of_range(span: Span) -> Self {
+        Self {
+            kind: ParseErrorKind::IntOutOfRange,
+            span,
+            expected: Vec::new(),
+            message: format!(
+                "integer is out of range, ints range from {} to {}",
+                i64::MIN,
+                i64::MAX
+            ),
+            label: "out of range".to_string(),
+        }
+    }
+
+    /// The error as a [`Diagnostic`] to render against the parsed source.
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            message: self.message.clone(),
+            span: self.span,
+            label: self.label.clone(),
+        }
+    }
+}
+
+/// Lists token kinds as "`a`, `b` or `c`".
+fn describe(kinds: &[TokenKind]) -> String {
+    alternatives(kinds.iter().map(ToString::to_string).collect())
+}
+
+fn alternatives(mut names: Vec<String>) -> String {
+    match names.pop() {
+        Some(last) if names.is_empty() => last,
+        Some(last) => format!("{} or {last}", names.join(", ")),
+        None => String::new(),
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum AST {
+    Select(Box<Query>),
     Create(EntityDescription, HashMap<String, Value>),
-    Link(EntityDescription, EntityDescription),
+    /// Source, optional edge label and target of the new edge.
+    Link(EntityDescription, Option<String>, EntityDescription),
+    DefineLabel(String, Vec<PropertyDefinition>),
+    /// Edge label, source label and target label.
+    DefineEdge(String, String, String),
+    CreateConstraint(Constraint),
+    Begin,
+    Commit,
+    Rollback,
+}
+
+/// A `select` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Whether duplicate rows are left out, as with `select distinct`.
+    pub distinct: bool,
+    pub projection: Projection,
+    pub path: PathExpression,
+    pub filter: Option<FilterExpression>,
+    /// Expressions of `group by`, whose values tell groups apart.
+    pub group: Vec<Expression>,
+    /// Filter on groups, which may use aggregates.
+    pub having: Option<FilterExpression>,
+    /// Sort keys of `order by`, most significant first.
+    pub order: Vec<SortKey>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub expression: Expression,
+    pub direction: Direction,
 }
 
-#[derive(Debug)]
-pub(crate) enum Projection {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Projection {
     All,
-    Fields(Vec<String>),
+    Fields(Vec<Field>),
+}
+
+/// Projected expression and the name given to its column with `as`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub expression: Expression,
+    pub alias: Option<String>,
 }
 
-#[derive(Debug)]
-pub(crate) enum EntityDescription {
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityDescription {
     NoId(String),
     WithId(String, String),
 }
 
-#[derive(Debug)]
-pub(crate) struct PathExpression(EntityDescription, Vec<EntityDescription>);
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathExpression(pub EntityDescription, pub Vec<EntityDescription>);
 
-#[derive(Debug)]
-pub(crate) enum FilterExpression {
-    Plain(String, Operator, Value),
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpression {
+    Comparison(Expression, Operator, Expression),
+    IsNull(Expression),
+    IsNotNull(Expression),
+    Not(Box<FilterExpression>),
     And(Box<FilterExpression>, Box<FilterExpression>),
     Or(Box<FilterExpression>, Box<FilterExpression>),
 }
 
-#[derive(Debug)]
-pub(crate) enum Operator {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
     Equals,
     NotEquals,
     SmallerThan,
     GreaterThan,
     SmallerThanOrEqual,
     GreaterThanOrEqual,
+    /// Whether a string matches a pattern where `%` stands for any text and `_` for any one
+    /// character, unless escaped by `\`.
     Like,
+    /// [`Like`](Operator::Like) ignoring case.
+    ILike,
+    In,
+    NotIn,
+    Contains,
+    StartsWith,
+    EndsWith,
+    /// Whether a string matches a regular expression anywhere.
+    Matches,
 }
 
 impl TryFrom<Token> for Operator {
@@ -67,279 +251,2241 @@ impl TryFrom<Token> for Operator {
     }
 }
 
-#[derive(Debug)]
-pub(crate) enum Value {
-    String(String),
-    Int(usize),
-    Float(f64),
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Equals => "=",
+            Operator::NotEquals => "!=",
+            Operator::SmallerThan => "<",
+            Operator::GreaterThan => ">",
+            Operator::SmallerThanOrEqual => "<=",
+            Operator::GreaterThanOrEqual => ">=",
+            Operator::Like => "like",
+            Operator::ILike => "ilike",
+            Operator::In => "in",
+            Operator::NotIn => "not in",
+            Operator::Contains => "contains",
+            Operator::StartsWith => "starts with",
+            Operator::EndsWith => "ends with",
+            Operator::Matches => "matches",
+        };
+
+        write!(f, "{symbol}")
+    }
 }
 
-impl TryFrom<Token> for Value {
-    type Error = ();
+/// Value computed from the properties of a single record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(Value),
+    Property(String),
+    Negate(Box<Expression>),
+    Arithmetic(Box<Expression>, ArithmeticOperator, Box<Expression>),
+    /// Function name and arguments, such as `lower(name)`.
+    Call(String, Vec<Expression>),
+    /// Property of a path step, as in `book.title`, or entry of a map, as in `meta.isbn`.
+    Access(Box<Expression>, String),
+    /// Aggregate over the records of a group, whether it only takes distinct values, and its
+    /// argument, which is missing for `count(*)`.
+    Aggregate(AggregateFunction, bool, Option<Box<Expression>>),
+}
 
-    fn try_from(value: Token) -> Result<Self, Self::Error> {
-        match value {
-            Token::StringLiteral(string) => Ok(Value::String(string)),
-            Token::IntLiteral(int) => Ok(Value::Int(int)),
-            Token::FloatLiteral(float) => Ok(Value::Float(float)),
-            _ => Err(()),
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Collect,
+}
+
+impl AggregateFunction {
+    fn named(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "collect" => Some(AggregateFunction::Collect),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct Parser<'t> {
-    input: Peekable<Lexer<'t>>,
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Collect => "collect",
+        };
+
+        write!(f, "{name}")
+    }
 }
 
-impl<'t> Parser<'t> {
-    pub fn new(input: Lexer<'t>) -> Self {
-        Self { input: input.peekable() }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl ArithmeticOperator {
+    fn binding(self) -> u8 {
+        match self {
+            ArithmeticOperator::Add | ArithmeticOperator::Subtract => SUM_BINDING,
+            ArithmeticOperator::Multiply
+            | ArithmeticOperator::Divide
+            | ArithmeticOperator::Remainder => PRODUCT_BINDING,
+        }
     }
+}
 
-    pub fn parse(&mut self) -> Result<AST, ParseError> {
-        let (first, _) = self.input.peek().ok_or(ParseError::UnexpectedEOF)?;
+impl TryFrom<&Token> for ArithmeticOperator {
+    type Error = ();
 
-        match first {
-            Token::Select => self.expect_select_statement(),
-            Token::Create => self.expect_create_statement(),
-            Token::Link => self.expect_link_statement(),
-            _ => Err(ParseError::UnexpectedToken(first.clone())),
+    fn try_from(value: &Token) -> Result<Self, Self::Error> {
+        match value {
+            Token::Plus => Ok(ArithmeticOperator::Add),
+            Token::Minus => Ok(ArithmeticOperator::Subtract),
+            Token::Asterisk => Ok(ArithmeticOperator::Multiply),
+            Token::Slash => Ok(ArithmeticOperator::Divide),
+            Token::Percent => Ok(ArithmeticOperator::Remainder),
+            _ => Err(()),
         }
     }
+}
 
-    fn expect_select_statement(&mut self) -> Result<AST, ParseError> {
-        self.expect_token_type(TokenKind::Select)?;
-        let projection = self.expect_projection()?;
-        self.expect_token_type(TokenKind::From)?;
-        let path_expression = self.expect_path_expression()?;
+impl fmt::Display for ArithmeticOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ArithmeticOperator::Add => "+",
+            ArithmeticOperator::Subtract => "-",
+            ArithmeticOperator::Multiply => "*",
+            ArithmeticOperator::Divide => "/",
+            ArithmeticOperator::Remainder => "%",
+        };
+
+        write!(f, "{symbol}")
+    }
+}
 
-        let peeked_next = self.input.peek();
+/// Writes the expression as MQL, with only the parentheses its precedence requires.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Literal(value) => write!(f, "{value}"),
+            Expression::Property(name) => write!(f, "{}", Identifier(name)),
+            Expression::Negate(operand) => {
+                let text = operand.to_string();
+                // `-5` would be a literal and `--5` a comment
+                if matches!(**operand, Expression::Arithmetic(..))
+                    || text.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+                {
+                    write!(f, "-({text})")
+                } else {
+                    write!(f, "-{text}")
+                }
+            }
+            Expression::Arithmetic(lhs, operator, rhs) => {
+                let binding = operator.binding();
+                let needs_parentheses = |operand: &Expression, right: bool| match operand {
+                    Expression::Arithmetic(_, inner, _) => {
+                        inner.binding() < binding || (right && inner.binding() == binding)
+                    }
+                    _ => false,
+                };
 
-        if let Some((token, span)) = peeked_next {
-            match token {
-                Token::Semicolon => {
-                    self.input.next();
-                    return Ok(AST::Select(projection, path_expression, None));
-                },
-                _ => {
-                    let filter_expression = self.expect_filter_condition()?;
-                    // TODO: actually pass in what expect_filter_condition returns
-                    return Ok(AST::Select(projection, path_expression, Some(filter_expression)));
-                },
+                if needs_parentheses(lhs, false) {
+                    write!(f, "({lhs})")?;
+                } else {
+                    write!(f, "{lhs}")?;
+                }
+                write!(f, " {operator} ")?;
+                if needs_parentheses(rhs, true) {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            }
+            Expression::Call(function, arguments) => {
+                write!(f, "{}(", Identifier(function))?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{argument}")?;
+                }
+                write!(f, ")")
+            }
+            Expression::Access(target, name) => match **target {
+                Expression::Arithmetic(..) | Expression::Negate(_) => {
+                    write!(f, "({target}).{}", Identifier(name))
+                }
+                _ => write!(f, "{target}.{}", Identifier(name)),
+            },
+            Expression::Aggregate(function, distinct, argument) => {
+                write!(f, "{function}(")?;
+                if *distinct {
+                    write!(f, "distinct ")?;
+                }
+                match argument {
+                    Some(argument) => write!(f, "{argument})"),
+                    None => write!(f, "*)"),
+                }
             }
         }
+    }
+}
 
-        Err(ParseError::UnexpectedEOF)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    List(Vec<Value>),
+    /// Nested map such as `{ city = "Oslo" }`, only valid as a value.
+    Map(BTreeMap<String, Value>),
+    /// Placeholder `$name`, replaced by the value given for it when a prepared statement runs.
+    Parameter(String),
+}
 
+/// Writes the value as an MQL literal.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(string) => {
+                write!(f, "\"")?;
+                for c in string.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\t' => write!(f, "\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Int(int) => write!(f, "{int}"),
+            // a literal too large for a float reads as infinity
+            Value::Float(float) if float.is_infinite() => match float.is_sign_negative() {
+                true => write!(f, "-1e999"),
+                false => write!(f, "1e999"),
+            },
+            Value::Float(float) => write!(f, "{float:?}"),
+            Value::Bool(bool) => write!(f, "{bool}"),
+            Value::Null => write!(f, "null"),
+            Value::Parameter(name) => write!(f, "${name}"),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {} = {value}", Identifier(key))?;
+                }
+                if entries.is_empty() {
+                    write!(f, "}}")
+                } else {
+                    write!(f, " }}")
+                }
+            }
+        }
     }
+}
 
-    fn expect_create_statement(&mut self) -> Result<AST, ParseError> {
-        let _ = self.expect_token_type(TokenKind::Create)?;
-        let entity_description = self.expect_entity_description()?;
-        let values = self.expect_key_value_pairs()?;
-        let _ = self.expect_token_type(TokenKind::Semicolon);
-
-        Ok(AST::Create(entity_description, values))
+impl From<graph::Value> for Value {
+    fn from(value: graph::Value) -> Self {
+        match value {
+            graph::Value::String(string) => Value::String(string),
+            graph::Value::Int(int) => Value::Int(int),
+            graph::Value::Float(float) => Value::Float(float),
+            graph::Value::Bool(bool) => Value::Bool(bool),
+            graph::Value::List(values) => {
+                Value::List(values.into_iter().map(Value::from).collect())
+            }
+            graph::Value::Map(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, Value::from(value)))
+                    .collect(),
+            ),
+        }
     }
+}
 
-    fn expect_link_statement(&mut self) -> Result<AST, ParseError> {
-        let _ = self.expect_token_type(TokenKind::Link);
-        let lhs_entity_description = self.expect_entity_description()?;
-        let _ = self.expect_token_type(TokenKind::ArrowRight);
-        let rhs_entity_description = self.expect_entity_description()?;
+/// Values written as a single token. Ints that do not fit are rejected by the parser.
+impl TryFrom<Token> for Value {
+    type Error = ();
 
-        Ok(AST::Link(lhs_entity_description, rhs_entity_description))
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        match value {
+            Token::StringLiteral(string) => Ok(Value::String(string)),
+            Token::IntLiteral(int) => Ok(Value::Int(i64::try_from(int).unwrap_or(i64::MAX))),
+            Token::FloatLiteral(float) => Ok(Value::Float(float)),
+            Token::BoolLiteral(bool) => Ok(Value::Bool(bool)),
+            Token::Null => Ok(Value::Null),
+            Token::Parameter(name) => Ok(Value::Parameter(name)),
+            _ => Err(()),
+        }
     }
+}
 
-    fn expect_projection(&mut self) -> Result<Projection, ParseError> {
-        let peeked_next = self.input.peek();
-
-        match peeked_next {
-            Some((token, _)) => {
-                if let Token::Asterisk = token {
-                    self.input.next();
-                    return Ok(Projection::All)
-                }
-
-                let mut fields = Vec::new();
-
-                loop {
-                    // peek first then consume if identifier to prevent the parser to consume the
-                    // from token that comes after a projection
-                    let (token, _) = self.input.peek().ok_or(ParseError::UnexpectedEOF)?;
-                    if let Token::Identifier(field) = token {
-                        fields.push(field.to_string());
-                        self.input.next();
-                        if let Some((peeked_next, _)) = self.input.peek() {
-                            if *peeked_next == Token::Comma {
-                                self.input.next();
-                                continue;
-                            }
-                        }
-                    } else {
-                        break;
-                    }
-                }
+/// Name written as an MQL identifier, in backticks unless it reads as one bare.
+struct Identifier<'n>(&'n str);
 
-                if fields.is_empty() {
-                    return Err(ParseError::InvalidProjection);
-                }
+impl fmt::Display for Identifier<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars();
+        // a bare `distinct` leading a projection or an aggregate argument may read as `distinct`
+        let bare = chars.next().is_some_and(char::is_alphabetic)
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && lexer::keyword(self.0).is_none()
+            && !self.0.eq_ignore_ascii_case("distinct");
 
-                Ok(Projection::Fields(fields))
-            },
-            None => Err(ParseError::UnexpectedEOF),
+        match bare {
+            true => write!(f, "{}", self.0),
+            false => write!(f, "`{}`", self.0.replace('`', "``")),
         }
     }
+}
 
-    fn expect_path_expression(&mut self) -> Result<PathExpression, ParseError> {
-        let source = self.expect_entity_description()?;
-        let mut path = Vec::new();
+/// Writes `items` separated by `, `.
+fn list<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl IntoIterator<Item = T>,
+) -> fmt::Result {
+    for (index, item) in items.into_iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{item}")?;
+    }
 
-        loop {
-            if let Some((peeked_next, _)) = self.input.peek() {
-                match peeked_next {
-                    Token::ArrowRight => {
-                        self.input.next();
-                        let step = self.expect_entity_description()?;
-                        path.push(step);
-                        continue;
+    Ok(())
+}
+
+/// Writes the statement as canonical MQL, without the `;` that ends it.
+impl fmt::Display for AST {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AST::Select(query) => write!(f, "{query}"),
+            AST::Create(entity, properties) => {
+                // properties are written in name order, as they have no order of their own
+                let sorted = properties.iter().collect::<BTreeMap<_, _>>();
+                write!(f, "create {entity} {{")?;
+                for (index, (key, value)) in sorted.into_iter().enumerate() {
+                    let separator = if index > 0 { "," } else { "" };
+                    write!(f, "{separator} {} = {value}", Identifier(key))?;
+                }
+                match properties.is_empty() {
+                    true => write!(f, "}}"),
+                    false => write!(f, " }}"),
+                }
+            }
+            AST::Link(source, label, target) => {
+                write!(f, "link {source} -> ")?;
+                if let Some(label) = label {
+                    write!(f, "{} -> ", Identifier(label))?;
+                }
+                write!(f, "{target}")
+            }
+            AST::DefineLabel(label, properties) => {
+                write!(f, "define label {} {{", Identifier(label))?;
+                for (index, property) in properties.iter().enumerate() {
+                    let separator = if index > 0 { "," } else { "" };
+                    write!(
+                        f,
+                        "{separator} {}: {}",
+                        Identifier(&property.name),
+                        property.ty
+                    )?;
+                    if property.required {
+                        write!(f, " required")?;
                     }
-                    _ => break,
                 }
-            } else {
-                return Err(ParseError::UnexpectedEOF);
+                match properties.is_empty() {
+                    true => write!(f, "}}"),
+                    false => write!(f, " }}"),
+                }
             }
+            AST::DefineEdge(label, from, to) => write!(
+                f,
+                "define edge {} from {} to {}",
+                Identifier(label),
+                Identifier(from),
+                Identifier(to)
+            ),
+            AST::CreateConstraint(constraint) => {
+                let kind = match constraint {
+                    Constraint::Unique { .. } => "unique",
+                    Constraint::Exists { .. } => "exists",
+                };
+                write!(
+                    f,
+                    "create constraint {kind} on {}({})",
+                    Identifier(constraint.label()),
+                    Identifier(constraint.property())
+                )
+            }
+            AST::Begin => write!(f, "begin"),
+            AST::Commit => write!(f, "commit"),
+            AST::Rollback => write!(f, "rollback"),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "select ")?;
+        if self.distinct {
+            write!(f, "distinct ")?;
         }
+        write!(f, "{} from {}", self.projection, self.path)?;
 
-        Ok(PathExpression(source, path))
+        if let Some(filter) = &self.filter {
+            write!(f, " where {filter}")?;
+        }
+        if !self.group.is_empty() {
+            write!(f, " group by ")?;
+            list(f, &self.group)?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " having {having}")?;
+        }
+        if !self.order.is_empty() {
+            write!(f, " order by ")?;
+            list(f, &self.order)?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " limit {limit}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " offset {offset}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Projection::All => write!(f, "*"),
+            Projection::Fields(fields) => list(f, fields),
+        }
     }
+}
 
-    fn expect_entity_description(&mut self) -> Result<EntityDescription, ParseError> {
-        let typename = self.expect_token_type(TokenKind::Identifier)?;
-        if let Some((peeked_next, _)) = self.input.peek() {
-            if *peeked_next == Token::Colon {
-                self.input.next();
-                let id = self.expect_token_type(TokenKind::Identifier)?;
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)?;
+        match &self.alias {
+            Some(alias) => write!(f, " as {}", Identifier(alias)),
+            None => Ok(()),
+        }
+    }
+}
 
-                if let Token::Identifier(id) = id && let Token::Identifier(typename) = typename {
-                    return Ok(EntityDescription::WithId(id, typename));
-                }
+/// Writes the sort key with `desc` if descending, ascending being the default.
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Ascending => write!(f, "{}", self.expression),
+            Direction::Descending => write!(f, "{} desc", self.expression),
+        }
+    }
+}
 
-                unreachable!()
+impl fmt::Display for EntityDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityDescription::NoId(label) => write!(f, "{}", Identifier(label)),
+            EntityDescription::WithId(id, label) => {
+                write!(f, "{}:{}", Identifier(label), Identifier(id))
             }
         }
+    }
+}
 
-        if let Token::Identifier(typename) = typename {
-            return Ok(EntityDescription::NoId(typename.to_string()))
+impl fmt::Display for PathExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let PathExpression(source, steps) = self;
+        write!(f, "{source}")?;
+        for step in steps {
+            write!(f, " -> {step}")?;
         }
 
-        unreachable!()
+        Ok(())
     }
+}
 
-    fn expect_filter_condition(&mut self) -> Result<FilterExpression, ParseError> {
-        let _ = self.expect_token_type(TokenKind::Where)?;
-        let filter_expression = self.expect_expression()?;
-        let _ = self.expect_token_type(TokenKind::Semicolon);
+/// Writes the filter as MQL, with only the parentheses its precedence requires.
+impl fmt::Display for FilterExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `and` and `or` are left associative, so an equal operator on the right is grouped
+        let grouped = |filter: &FilterExpression, binding: u8, right: bool| match filter {
+            FilterExpression::Or(..) => OR_BINDING < binding || right && binding == OR_BINDING,
+            FilterExpression::And(..) => AND_BINDING < binding || right && binding == AND_BINDING,
+            _ => false,
+        };
+        let operand =
+            |f: &mut fmt::Formatter<'_>, filter: &FilterExpression, grouped: bool| match grouped {
+                true => write!(f, "({filter})"),
+                false => write!(f, "{filter}"),
+            };
 
-        Ok(filter_expression)
+        match self {
+            FilterExpression::Comparison(lhs, operator, rhs) => write!(f, "{lhs} {operator} {rhs}"),
+            FilterExpression::IsNull(expression) => write!(f, "{expression} is null"),
+            FilterExpression::IsNotNull(expression) => write!(f, "{expression} is not null"),
+            FilterExpression::Not(filter) => {
+                write!(f, "not ")?;
+                operand(f, filter, grouped(filter, NOT_BINDING, false))
+            }
+            FilterExpression::And(lhs, rhs) | FilterExpression::Or(lhs, rhs) => {
+                let (binding, name) = match self {
+                    FilterExpression::And(..) => (AND_BINDING, "and"),
+                    _ => (OR_BINDING, "or"),
+                };
+                operand(f, lhs, grouped(lhs, binding, false))?;
+                write!(f, " {name} ")?;
+                operand(f, rhs, grouped(rhs, binding, true))
+            }
+        }
     }
+}
 
-    fn expect_expression(&mut self) -> Result<FilterExpression, ParseError> {
-        let expression = match self.input.next() {
-            Some((Token::LParen, _)) => {
-                let expression = self.expect_expression()?;
-                let _ = self.expect_token_type(TokenKind::RParen)?;
+/// Operand of a logical operator, or a parenthesised value not yet known to be compared.
+enum Operand {
+    Filter(FilterExpression),
+    Value(Expression),
+}
 
-                Ok(expression)
-            },
-            Some((token, _)) => {
-                if let Token::Identifier(identifier) = token.clone() {
-                    let operator = self.expect_operator()?;
-                    let value = self.expect_value()?;
-                    Ok(FilterExpression::Plain(identifier, operator, value))
-                } else {
-                    Err(ParseError::UnexpectedToken(token))
-                }
-            },
-            None => Err(ParseError::UnexpectedEOF),
-        };
+const STATEMENTS: &[TokenKind] = &[
+    TokenKind::Select,
+    TokenKind::Create,
+    TokenKind::Link,
+    TokenKind::Define,
+    TokenKind::Begin,
+    TokenKind::Commit,
+    TokenKind::Rollback,
+];
 
-        let mut cloned_input = self.input.clone();
-        let peeked_next = cloned_input.peek();
-        match peeked_next {
-            Some((token @ (Token::And | Token::Or), _)) => {
-                let rhs = self.expect_expression()?;
-                match token {
-                    Token::And => Ok(FilterExpression::And(Box::new(expression?), Box::new(rhs))),
-                    Token::Or => Ok(FilterExpression::Or(Box::new(expression?), Box::new(rhs))),
-                    _ => unreachable!(),
-                }
-            },
-            // TODO: done here?
-            _ => expression,
-        }
+/// How tightly the logical operators in filters bind their operands, loosest first.
+const OR_BINDING: u8 = 1;
+const AND_BINDING: u8 = 2;
+const NOT_BINDING: u8 = 3;
+
+/// How tightly the arithmetic operators bind their operands, loosest first.
+const SUM_BINDING: u8 = 1;
+const PRODUCT_BINDING: u8 = 2;
+const NEGATE_BINDING: u8 = 3;
+
+/// Tokens that can start an [`Expression`].
+const EXPRESSIONS: &[TokenKind] = &[
+    TokenKind::Identifier,
+    TokenKind::StringLiteral,
+    TokenKind::IntLiteral,
+    TokenKind::FloatLiteral,
+    TokenKind::BoolLiteral,
+    TokenKind::Null,
+    TokenKind::Parameter,
+    TokenKind::LBracket,
+    TokenKind::Minus,
+    TokenKind::LParen,
+];
+
+/// Tokens that can start a comparison. Operators written as words are identifiers, apart from
+/// `like` and the `not` of `not in`.
+const OPERATORS: &[TokenKind] = &[
+    TokenKind::Equals,
+    TokenKind::NotEquals,
+    TokenKind::SmallerThan,
+    TokenKind::GreaterThan,
+    TokenKind::SmallerThanOrEquals,
+    TokenKind::GreaterThanOrEquals,
+    TokenKind::Like,
+    TokenKind::Not,
+    TokenKind::Identifier,
+];
+
+/// Comparisons written as contextual keywords, as errors name them.
+const WORD_OPERATORS: &[&str] = &[
+    "ilike",
+    "in",
+    "not in",
+    "contains",
+    "starts with",
+    "ends with",
+    "matches",
+    "is",
+];
+
+const VALUES: &[TokenKind] = &[
+    TokenKind::StringLiteral,
+    TokenKind::IntLiteral,
+    TokenKind::FloatLiteral,
+    TokenKind::BoolLiteral,
+    TokenKind::Null,
+    TokenKind::Parameter,
+    TokenKind::Minus,
+    TokenKind::LBracket,
+    TokenKind::LBrace,
+];
+
+/// Tokens of a [`Lexer`] without the comments, which the grammar never sees.
+#[derive(Debug, Clone)]
+struct Code<'t>(Lexer<'t>);
+
+impl Iterator for Code<'_> {
+    type Item = (Token, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find(|(token, _)| !token.is_trivia())
     }
+}
+
+/// Parses MQL statements from a [`Lexer`].
+///
+/// Statements are separated by `;`, which may be left out after the last one. Iterating over the
+/// parser yields one statement at a time, so large scripts can be executed while they are parsed.
+///
+/// After an error the parser skips ahead to the next `;` or statement keyword and carries on
+/// from there, so one pass over a script reports every broken statement.
+#[derive(Debug, Clone)]
+pub struct Parser<'t> {
+    input: Peekable<Code<'t>>,
+    /// End of the last consumed token, where errors about missing tokens point.
+    end: usize,
+    /// Missing `;` after the statement just returned, reported on the next call.
+    pending: Option<ParseError>,
+    /// Span of the statement just returned, from its first token up to its `;` if it has one.
+    last: Span,
+}
 
-    fn expect_operator(&mut self) -> Result<Operator, ParseError> {
-        match self.input.next() {
-            Some((token, _)) => Operator::try_from(token.clone()).map_err(|_| ParseError::UnexpectedToken(token)),
-            None => Err(ParseError::UnexpectedEOF),
+impl<'t> Parser<'t> {
+    pub fn new(input: Lexer<'t>) -> Self {
+        Self {
+            input: Code(input).peekable(),
+            end: 0,
+            pending: None,
+            last: Span::new(0, 0),
         }
     }
 
-    fn expect_value(&mut self) -> Result<Value, ParseError> {
-        match self.input.next() {
-            Some((token, _)) => Value::try_from(token.clone()).map_err(|_| ParseError::UnexpectedToken(token)),
-            None => Err(ParseError::UnexpectedEOF)
+    /// Parses one statement and its optional `;`, leaving whatever follows unparsed.
+    pub fn parse(&mut self) -> Result<AST, ParseError> {
+        let statement = self.expect_statement()?;
+        self.next_if(TokenKind::Semicolon);
+
+        Ok(statement)
+    }
+
+    /// Parses `source`, which must hold a single statement.
+    pub fn parse_one(source: &'t str) -> Result<AST, ParseError> {
+        // the iterator, unlike `parse`, reports anything left over after the statement
+        let mut parser = Parser::new(Lexer::new(source));
+        let statement = parser.next().unwrap_or_else(|| parser.parse())?;
+        if let Some(Err(error)) = parser.next() {
+            return Err(error);
         }
+
+        Ok(statement)
     }
 
-    fn expect_key_value_pairs(&mut self) -> Result<HashMap<String, Value>, ParseError> {
-        let mut values = HashMap::new();
+    /// Parses every statement up to the end of the input, stopping at the first error.
+    pub fn parse_all(self) -> Result<Vec<AST>, ParseError> {
+        self.collect()
+    }
 
-        let _ = self.expect_token_type(TokenKind::RBrace);
+    /// Parses every statement up to the end of the input, returning the statements that parsed,
+    /// each with its span, along with the errors of those that did not.
+    pub fn parse_recovering(mut self) -> (Vec<(AST, Span)>, Vec<ParseError>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
 
-        loop {
-            let peeked_next = self.input.peek();
-            match peeked_next {
-                Some((Token::LBrace, _)) => return Ok(values),
-                Some((_, _)) => {
-                    let pair = self.expect_key_value_pair()?;
-                    values.insert(pair.0, pair.1);
-                    if let Some((Token::Comma, _)) = self.input.peek() {
-                        self.input.next();
-                        continue;
-                    } else {
-                        break;
-                    }
-                }
-                _ => break,
+        while let Some(statement) = self.next() {
+            match statement {
+                Ok(statement) => statements.push((statement, self.last)),
+                Err(error) => errors.push(error),
             }
         }
 
-        self.expect_token_type(TokenKind::LBrace)?;
+        (statements, errors)
+    }
 
-        Ok(values)
+    fn expect_statement(&mut self) -> Result<AST, ParseError> {
+        match self.peek() {
+            Some(Token::Select) => self.expect_select_statement(),
+            Some(Token::Create) => self.expect_create_statement(),
+            Some(Token::Link) => self.expect_link_statement(),
+            Some(Token::Define) => self.expect_define_statement(),
+            Some(Token::Begin) => self.expect_transaction_statement(TokenKind::Begin, AST::Begin),
+            Some(Token::Commit) => {
+                self.expect_transaction_statement(TokenKind::Commit, AST::Commit)
+            }
+            Some(Token::Rollback) => {
+                self.expect_transaction_statement(TokenKind::Rollback, AST::Rollback)
+            }
+            _ => Err(self.unexpected(STATEMENTS)),
+        }
     }
 
-    fn expect_key_value_pair(&mut self) -> Result<(String, Value), ParseError> {
-        let key = if let Token::Identifier(identifier) = self.expect_token_type(TokenKind::Identifier)? {
-            identifier
-        } else {
-            unreachable!()
+    fn expect_select_statement(&mut self) -> Result<AST, ParseError> {
+        self.expect_token_type(TokenKind::Select)?;
+
+        let distinct = self.next_if_distinct();
+        let projection = self.expect_projection()?;
+        self.expect_token_type(TokenKind::From)?;
+        let path = self.expect_path_expression()?;
+
+        let filter = match self.peek() {
+            Some(Token::Where) => Some(self.expect_filter_condition()?),
+            _ => None,
         };
-        let _ = self.expect_token_type(TokenKind::Equals);
-        let value = self.expect_value()?;
 
-        Ok((key, value))
-    }
+        let mut group = Vec::new();
+        if self.next_if_keyword("group") {
+            self.expect_keyword(&["by"])?;
+            group.push(self.expect_value_expression(0)?);
+            while self.next_if(TokenKind::Comma).is_some() {
+                group.push(self.expect_value_expression(0)?);
+            }
+        }
+
+        let having = match self.next_if_keyword("having") {
+            true => Some(self.expect_expression()?),
+            false => None,
+        };
+
+        let mut order = Vec::new();
+        if self.next_if_keyword("order") {
+            self.expect_keyword(&["by"])?;
+            loop {
+                let expression = self.expect_value_expression(0)?;
+                let direction = if self.next_if_keyword("desc") {
+                    Direction::Descending
+                } else {
+                    self.next_if_keyword("asc");
+                    Direction::Ascending
+                };
+                order.push(SortKey {
+                    expression,
+                    direction,
+                });
+
+                if self.next_if(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+
+        let limit = match self.next_if_keyword("limit") {
+            true => Some(self.expect_count()?),
+            false => None,
+        };
+        let offset = match self.next_if_keyword("offset") {
+            true => Some(self.expect_count()?),
+            false => None,
+        };
+
+        let query = Query {
+            distinct,
+            projection,
+            path,
+            filter,
+            group,
+            having,
+            order,
+            limit,
+            offset,
+        };
+
+        Ok(AST::Select(Box::new(query)))
+    }
+
+    /// Number of rows, as given to `limit` and `offset`, which is an int like any other.
+    fn expect_count(&mut self) -> Result<usize, ParseError> {
+        match self.peek() {
+            Some(Token::IntLiteral(count)) if i64::try_from(*count).is_ok() => {
+                let count = *count;
+                self.advance();
+                Ok(count)
+            }
+            Some(Token::IntLiteral(_)) => {
+                let (_, span) = self.advance().expect("peeked");
+                Err(ParseError::int_out_of_range(span))
+            }
+            _ => Err(self.unexpected(&[TokenKind::IntLiteral])),
+        }
+    }
+
+    fn expect_create_statement(&mut self) -> Result<AST, ParseError> {
+        self.expect_token_type(TokenKind::Create)?;
+
+        // `constraint` is only a keyword when followed by the constraint kind, records labelled
+        // `constraint` are still created with `create constraint:id { ... }`
+        let mut lookahead = self.input.clone();
+        if let (Some((Token::Identifier(word), _)), Some((next, _))) =
+            (lookahead.next(), lookahead.next())
+            && word.eq_ignore_ascii_case("constraint")
+            && next.kind() == TokenKind::Identifier
+        {
+            return self.expect_constraint();
+        }
+
+        let entity_description = self.expect_entity_description()?;
+        let values = self.expect_key_value_pairs()?;
+
+        Ok(AST::Create(entity_description, values))
+    }
+
+    fn expect_constraint(&mut self) -> Result<AST, ParseError> {
+        self.expect_keyword(&["constraint"])?;
+        let kind = self.expect_keyword(&["unique", "exists"])?;
+        self.expect_keyword(&["on"])?;
+        let label = self.expect_identifier()?;
+        self.expect_token_type(TokenKind::LParen)?;
+        let property = self.expect_identifier()?;
+        self.expect_token_type(TokenKind::RParen)?;
+
+        let constraint = match kind {
+            "unique" => Constraint::Unique { label, property },
+            _ => Constraint::Exists { label, property },
+        };
+
+        Ok(AST::CreateConstraint(constraint))
+    }
+
+    fn expect_link_statement(&mut self) -> Result<AST, ParseError> {
+        self.expect_token_type(TokenKind::Link)?;
+        let lhs_entity_description = self.expect_entity_description()?;
+        self.expect_token_type(TokenKind::ArrowRight)?;
+        let mut rhs_entity_description = self.expect_entity_description()?;
+        let mut label = None;
+
+        // `link author:jk -> wrote -> book:hp1` names the edge between the two arrows
+        if let (Some(Token::ArrowRight), EntityDescription::NoId(_)) =
+            (self.peek(), &rhs_entity_description)
+        {
+            self.advance();
+            let EntityDescription::NoId(edge) = rhs_entity_description else {
+                unreachable!("matched above");
+            };
+            label = Some(edge);
+            rhs_entity_description = self.expect_entity_description()?;
+        }
+
+        Ok(AST::Link(
+            lhs_entity_description,
+            label,
+            rhs_entity_description,
+        ))
+    }
+
+    fn expect_transaction_statement(
+        &mut self,
+        kind: TokenKind,
+        statement: AST,
+    ) -> Result<AST, ParseError> {
+        self.expect_token_type(kind)?;
+
+        Ok(statement)
+    }
+
+    fn expect_define_statement(&mut self) -> Result<AST, ParseError> {
+        self.expect_token_type(TokenKind::Define)?;
+
+        let statement = match self.expect_keyword(&["label", "edge"])? {
+            "label" => {
+                let label = self.expect_identifier()?;
+                let properties = self.expect_property_definitions()?;
+
+                AST::DefineLabel(label, properties)
+            }
+            _ => {
+                let label = self.expect_identifier()?;
+                self.expect_token_type(TokenKind::From)?;
+                let from = self.expect_identifier()?;
+                self.expect_keyword(&["to"])?;
+                let to = self.expect_identifier()?;
+
+                AST::DefineEdge(label, from, to)
+            }
+        };
+
+        Ok(statement)
+    }
+
+    fn expect_property_definitions(&mut self) -> Result<Vec<PropertyDefinition>, ParseError> {
+        let mut definitions = Vec::new();
+        let mut expected = [TokenKind::Identifier, TokenKind::RBrace];
+
+        self.expect_token_type(TokenKind::LBrace)?;
+
+        while self
+            .peek()
+            .is_some_and(|token| token.kind() == TokenKind::Identifier)
+        {
+            let name = self.expect_identifier()?;
+            self.expect_token_type(TokenKind::Colon)?;
+            let ty =
+                match self.expect_keyword(&["string", "int", "float", "bool", "list", "map"])? {
+                    "string" => PropertyType::String,
+                    "int" => PropertyType::Int,
+                    "float" => PropertyType::Float,
+                    "bool" => PropertyType::Bool,
+                    "list" => PropertyType::List,
+                    _ => PropertyType::Map,
+                };
+            let required = self.next_if_keyword("required");
+
+            definitions.push(PropertyDefinition::new(name, ty, required));
+
+            if self.next_if(TokenKind::Comma).is_none() {
+                expected = [TokenKind::Comma, TokenKind::RBrace];
+                break;
+            }
+        }
+
+        if self.next_if(TokenKind::RBrace).is_none() {
+            return Err(self.unexpected(&expected));
+        }
+
+        Ok(definitions)
+    }
+
+    fn expect_projection(&mut self) -> Result<Projection, ParseError> {
+        if self.next_if(TokenKind::Asterisk).is_some() {
+            return Ok(Projection::All);
+        }
+
+        if !self
+            .peek()
+            .is_some_and(|token| EXPRESSIONS.contains(&token.kind()))
+        {
+            let mut error = self
+                .unexpected(&[TokenKind::Asterisk, TokenKind::Identifier])
+                .expecting("`*` or a list of expressions");
+            if let ParseErrorKind::UnexpectedToken(_) = error.kind {
+                error.kind = ParseErrorKind::InvalidProjection;
+            }
+            return Err(error);
+        }
+
+        let mut fields = vec![self.expect_field()?];
+        while self.next_if(TokenKind::Comma).is_some() {
+            fields.push(self.expect_field()?);
+        }
+
+        Ok(Projection::Fields(fields))
+    }
+
+    fn expect_field(&mut self) -> Result<Field, ParseError> {
+        let expression = self.expect_value_expression(0)?;
+        let alias = match self.next_if_keyword("as") {
+            true => Some(self.expect_identifier()?),
+            false => None,
+        };
+
+        Ok(Field { expression, alias })
+    }
+
+    fn expect_path_expression(&mut self) -> Result<PathExpression, ParseError> {
+        let source = self.expect_entity_description()?;
+        let mut path = Vec::new();
+
+        while self.next_if(TokenKind::ArrowRight).is_some() {
+            let step = self.expect_entity_description()?;
+            path.push(step);
+        }
+
+        Ok(PathExpression(source, path))
+    }
+
+    fn expect_entity_description(&mut self) -> Result<EntityDescription, ParseError> {
+        let typename = self.expect_identifier()?;
+
+        if self.next_if(TokenKind::Colon).is_some() {
+            let id = self.expect_identifier()?;
+            return Ok(EntityDescription::WithId(id, typename));
+        }
+
+        Ok(EntityDescription::NoId(typename))
+    }
+
+    fn expect_filter_condition(&mut self) -> Result<FilterExpression, ParseError> {
+        self.expect_token_type(TokenKind::Where)?;
+        let filter_expression = self.expect_expression()?;
+
+        Ok(filter_expression)
+    }
+
+    fn expect_expression(&mut self) -> Result<FilterExpression, ParseError> {
+        self.expect_filter(0)
+    }
+
+    /// Precedence climbing over `and`, `or` and `not`: parses an operand, then keeps folding in
+    /// operators that bind tighter than `minimum`. Binary operators are left associative.
+    fn expect_filter(&mut self, minimum: u8) -> Result<FilterExpression, ParseError> {
+        let filter = match self.expect_operand()? {
+            Operand::Filter(filter) => filter,
+            Operand::Value(_) => {
+                let symbols = OPERATORS
+                    .iter()
+                    .filter(|kind| !matches!(kind, TokenKind::Not | TokenKind::Identifier));
+                let words = WORD_OPERATORS.iter().map(|word| format!("`{word}`"));
+                let operators =
+                    alternatives(symbols.map(ToString::to_string).chain(words).collect());
+                return Err(self.unexpected(OPERATORS).expecting(&operators));
+            }
+        };
+
+        self.continue_filter(filter, minimum)
+    }
+
+    fn continue_filter(
+        &mut self,
+        mut filter: FilterExpression,
+        minimum: u8,
+    ) -> Result<FilterExpression, ParseError> {
+        loop {
+            let (binding, combine): (u8, fn(_, _) -> _) = match self.peek() {
+                Some(Token::Or) => (OR_BINDING, FilterExpression::Or),
+                Some(Token::And) => (AND_BINDING, FilterExpression::And),
+                _ => break,
+            };
+            if binding <= minimum {
+                break;
+            }
+
+            self.advance();
+            let rhs = self.expect_filter(binding)?;
+            filter = combine(Box::new(filter), Box::new(rhs));
+        }
+
+        Ok(filter)
+    }
+
+    /// Operand of a logical operator: a comparison, a negation or a parenthesised group.
+    ///
+    /// `(` may open a group of conditions as well as an arithmetic operand, as in
+    /// `(pages + 1) * 2 > 10`, which only becomes clear at the first comparison operator. So a
+    /// group without a comparison is returned as a value for the caller to compare.
+    fn expect_operand(&mut self) -> Result<Operand, ParseError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.advance();
+                let operand = self.expect_filter(NOT_BINDING)?;
+
+                Ok(Operand::Filter(FilterExpression::Not(Box::new(operand))))
+            }
+            Some(Token::LParen) => {
+                self.advance();
+                let operand = match self.expect_operand()? {
+                    Operand::Filter(filter) => Operand::Filter(self.continue_filter(filter, 0)?),
+                    value => value,
+                };
+                self.expect_token_type(TokenKind::RParen)?;
+
+                match operand {
+                    Operand::Filter(filter) => Ok(Operand::Filter(filter)),
+                    Operand::Value(value) => {
+                        let value = self.continue_access(value)?;
+                        let value = self.continue_value_expression(value, 0)?;
+                        self.expect_comparison(value)
+                    }
+                }
+            }
+            _ => {
+                let value = self.expect_value_expression(0)?;
+                self.expect_comparison(value)
+            }
+        }
+    }
+
+    /// Compares `lhs` with what follows, if a comparison operator follows.
+    fn expect_comparison(&mut self, lhs: Expression) -> Result<Operand, ParseError> {
+        if self.next_if_keyword("is") {
+            let negated = self.next_if(TokenKind::Not).is_some();
+            self.expect_token_type(TokenKind::Null)?;
+
+            return Ok(Operand::Filter(match negated {
+                true => FilterExpression::IsNotNull(lhs),
+                false => FilterExpression::IsNull(lhs),
+            }));
+        }
+
+        let Some(operator) = self.next_operator()? else {
+            return Ok(Operand::Value(lhs));
+        };
+        let rhs = self.expect_value_expression(0)?;
+
+        Ok(Operand::Filter(FilterExpression::Comparison(
+            lhs, operator, rhs,
+        )))
+    }
+
+    /// Precedence climbing over arithmetic, like [`expect_filter`](Self::expect_filter).
+    fn expect_value_expression(&mut self, minimum: u8) -> Result<Expression, ParseError> {
+        let expression = match self.peek() {
+            Some(Token::Minus) => {
+                // `-5` is a literal, so that the most negative int can be written
+                let mut lookahead = self.input.clone();
+                lookahead.next();
+                if let Some((Token::IntLiteral(_) | Token::FloatLiteral(_), _)) = lookahead.next() {
+                    Expression::Literal(self.expect_value()?)
+                } else {
+                    self.advance();
+                    let operand = self.expect_value_expression(NEGATE_BINDING)?;
+
+                    Expression::Negate(Box::new(operand))
+                }
+            }
+            Some(Token::LParen) => {
+                self.advance();
+                let expression = self.expect_value_expression(0)?;
+                self.expect_token_type(TokenKind::RParen)?;
+
+                expression
+            }
+            Some(token) if token.kind() == TokenKind::Identifier => {
+                let name = self.expect_identifier()?;
+                if self.next_if(TokenKind::LParen).is_none() {
+                    Expression::Property(name)
+                } else if let Some(function) = AggregateFunction::named(&name) {
+                    self.expect_aggregate(function)?
+                } else {
+                    let mut arguments = Vec::new();
+                    if self.next_if(TokenKind::RParen).is_none() {
+                        arguments.push(self.expect_value_expression(0)?);
+                        while self.next_if(TokenKind::Comma).is_some() {
+                            arguments.push(self.expect_value_expression(0)?);
+                        }
+                        self.expect_token_type(TokenKind::RParen)?;
+                    }
+
+                    // function names are case-insensitive, like keywords
+                    Expression::Call(name.to_ascii_lowercase(), arguments)
+                }
+            }
+            Some(Token::LBracket) => Expression::Literal(self.expect_value()?),
+            Some(token) if Value::try_from(token.clone()).is_ok() => {
+                Expression::Literal(self.expect_value()?)
+            }
+            _ => return Err(self.unexpected(EXPRESSIONS)),
+        };
+
+        let expression = self.continue_access(expression)?;
+        self.continue_value_expression(expression, minimum)
+    }
+
+    /// Applies any `.name` accesses following `expression`, which bind tighter than operators.
+    fn continue_access(&mut self, mut expression: Expression) -> Result<Expression, ParseError> {
+        while self.next_if(TokenKind::Dot).is_some() {
+            let name = self.expect_identifier()?;
+            expression = Expression::Access(Box::new(expression), name);
+        }
+
+        Ok(expression)
+    }
+
+    /// Rest of an aggregate after its opening parenthesis, such as `distinct pages)`.
+    fn expect_aggregate(&mut self, function: AggregateFunction) -> Result<Expression, ParseError> {
+        if function == AggregateFunction::Count && self.next_if(TokenKind::Asterisk).is_some() {
+            self.expect_token_type(TokenKind::RParen)?;
+            return Ok(Expression::Aggregate(function, false, None));
+        }
+
+        let distinct = self.next_if_distinct();
+        let argument = self.expect_value_expression(0)?;
+        self.expect_token_type(TokenKind::RParen)?;
+
+        Ok(Expression::Aggregate(
+            function,
+            distinct,
+            Some(Box::new(argument)),
+        ))
+    }
+
+    /// Consumes `distinct` if it modifies what follows, rather than being a property itself as in
+    /// `select distinct from x`.
+    fn next_if_distinct(&mut self) -> bool {
+        let mut lookahead = self.input.clone();
+        let distinct = match (lookahead.next(), lookahead.next()) {
+            (Some((Token::Identifier(word), _)), Some((token, _)))
+                if word.eq_ignore_ascii_case("distinct") =>
+            {
+                token == Token::Asterisk || EXPRESSIONS.contains(&token.kind())
+            }
+            _ => false,
+        };
+        if distinct {
+            self.advance();
+        }
+
+        distinct
+    }
+
+    fn continue_value_expression(
+        &mut self,
+        mut expression: Expression,
+        minimum: u8,
+    ) -> Result<Expression, ParseError> {
+        while let Some(operator) = self
+            .peek()
+            .and_then(|token| ArithmeticOperator::try_from(token).ok())
+        {
+            if operator.binding() <= minimum {
+                break;
+            }
+
+            self.advance();
+            let rhs = self.expect_value_expression(operator.binding())?;
+            expression = Expression::Arithmetic(Box::new(expression), operator, Box::new(rhs));
+        }
+
+        Ok(expression)
+    }
+
+    /// Consumes the comparison operator that comes next, if any. Operators written as words,
+    /// like `contains` or `not in`, are contextual keywords.
+    fn next_operator(&mut self) -> Result<Option<Operator>, ParseError> {
+        if let Some(operator) = self
+            .peek()
+            .and_then(|token| Operator::try_from(token.clone()).ok())
+        {
+            self.advance();
+            return Ok(Some(operator));
+        }
+
+        let mut lookahead = self.input.clone();
+        let operator = match (lookahead.next(), lookahead.next()) {
+            (Some((Token::Not, _)), Some((Token::Identifier(word), _)))
+                if word.eq_ignore_ascii_case("in") =>
+            {
+                self.advance();
+                Operator::NotIn
+            }
+            (Some((Token::Identifier(word), _)), _) => match word.to_ascii_lowercase().as_str() {
+                "in" => Operator::In,
+                "ilike" => Operator::ILike,
+                "contains" => Operator::Contains,
+                "starts" => Operator::StartsWith,
+                "ends" => Operator::EndsWith,
+                "matches" => Operator::Matches,
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        self.advance();
+
+        if let Operator::StartsWith | Operator::EndsWith = operator {
+            self.expect_keyword(&["with"])?;
+        }
+
+        Ok(Some(operator))
+    }
+
+    fn expect_value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                let (_, minus) = self.advance().expect("peeked");
+                match self.peek() {
+                    Some(Token::IntLiteral(int)) => {
+                        let int = *int;
+                        let (_, span) = self.advance().expect("peeked");
+                        0_i64
+                            .checked_sub_unsigned(int as u64)
+                            .map(Value::Int)
+                            .ok_or_else(|| ParseError::int_out_of_range(minus.merge(&span)))
+                    }
+                    Some(Token::FloatLiteral(float)) => {
+                        let float = -*float;
+                        self.advance();
+                        Ok(Value::Float(float))
+                    }
+                    _ => Err(self.unexpected(&[TokenKind::IntLiteral, TokenKind::FloatLiteral])),
+                }
+            }
+            Some(Token::IntLiteral(int)) if i64::try_from(*int).is_err() => {
+                let (_, span) = self.advance().expect("peeked");
+                Err(ParseError::int_out_of_range(span))
+            }
+            Some(Token::LBracket) => {
+                self.advance();
+                let mut values = Vec::new();
+                let mut expected = VALUES
+                    .iter()
+                    .cloned()
+                    .chain([TokenKind::RBracket])
+                    .collect::<Vec<_>>();
+
+                while self
+                    .peek()
+                    .is_some_and(|token| VALUES.contains(&token.kind()))
+                {
+                    values.push(self.expect_value()?);
+
+                    if self.next_if(TokenKind::Comma).is_none() {
+                        expected = vec![TokenKind::Comma, TokenKind::RBracket];
+                        break;
+                    }
+                }
+
+                if self.next_if(TokenKind::RBracket).is_none() {
+                    return Err(self.unexpected(&expected));
+                }
+
+                Ok(Value::List(values))
+            }
+            Some(Token::LBrace) => Ok(Value::Map(
+                self.expect_key_value_pairs()?.into_iter().collect(),
+            )),
+            _ => {
+                let value = self
+                    .peek()
+                    .and_then(|token| Value::try_from(token.clone()).ok());
+
+                match value {
+                    Some(value) => {
+                        self.advance();
+                        Ok(value)
+                    }
+                    None => Err(self.unexpected(VALUES)),
+                }
+            }
+        }
+    }
+
+    fn expect_key_value_pairs(&mut self) -> Result<HashMap<String, Value>, ParseError> {
+        let mut values = HashMap::new();
+        let mut expected = [TokenKind::Identifier, TokenKind::RBrace];
+
+        self.expect_token_type(TokenKind::LBrace)?;
+
+        while self
+            .peek()
+            .is_some_and(|token| token.kind() == TokenKind::Identifier)
+        {
+            let (key, value) = self.expect_key_value_pair()?;
+            values.insert(key, value);
+
+            if self.next_if(TokenKind::Comma).is_none() {
+                expected = [TokenKind::Comma, TokenKind::RBrace];
+                break;
+            }
+        }
+
+        if self.next_if(TokenKind::RBrace).is_none() {
+            return Err(self.unexpected(&expected));
+        }
+
+        Ok(values)
+    }
+
+    fn expect_key_value_pair(&mut self) -> Result<(String, Value), ParseError> {
+        let key = self.expect_identifier()?;
+        self.expect_token_type(TokenKind::Equals)?;
+        let value = self.expect_value()?;
+
+        Ok((key, value))
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, ParseError> {
+        match self.expect_token_type(TokenKind::Identifier)? {
+            Token::Identifier(identifier) | Token::QuotedIdentifier(identifier) => Ok(identifier),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Consumes an identifier that is one of the contextual keywords `words`.
+    fn expect_keyword(&mut self, words: &[&'static str]) -> Result<&'static str, ParseError> {
+        let word = match self.peek() {
+            Some(Token::Identifier(identifier)) => words
+                .iter()
+                .find(|&&word| word.eq_ignore_ascii_case(identifier))
+                .copied(),
+            _ => None,
+        };
+
+        if let Some(word) = word {
+            self.advance();
+            return Ok(word);
+        }
+
+        let names = alternatives(words.iter().map(|word| format!("`{word}`")).collect());
+        Err(self.unexpected(&[TokenKind::Identifier]).expecting(&names))
+    }
+
+    /// Consumes the contextual keyword `word` if it comes next.
+    fn next_if_keyword(&mut self, word: &str) -> bool {
+        let is_next = matches!(self.peek(), Some(Token::Identifier(identifier)) if identifier.eq_ignore_ascii_case(word));
+        if is_next {
+            self.advance();
+        }
+
+        is_next
+    }
 
     fn expect_token_type(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
-        match self.input.next() {
-            Some((token, _)) if token.kind() == kind => Ok(token),
-            Some((token, _)) => Err(ParseError::UnexpectedToken(token)),
-            None => Err(ParseError::UnexpectedEOF),
+        match self.next_if(kind.clone()) {
+            Some(token) => Ok(token),
+            None => Err(self.unexpected(&[kind])),
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.input.peek().map(|(token, _)| token)
+    }
+
+    fn advance(&mut self) -> Option<(Token, Span)> {
+        let (token, span) = self.input.next()?;
+        self.end = span.end();
+
+        Some((token, span))
+    }
+
+    fn next_if(&mut self, kind: TokenKind) -> Option<Token> {
+        if self.peek()?.kind() != kind {
+            return None;
+        }
+
+        self.advance().map(|(token, _)| token)
+    }
+
+    /// Skips the rest of a broken statement, up to and including its `;` or up to the keyword
+    /// starting the next one.
+    fn synchronize(&mut self) {
+        while let Some(token) = self.peek() {
+            match token.kind() {
+                TokenKind::Semicolon => {
+                    self.advance();
+                    break;
+                }
+                kind if STATEMENTS.contains(&kind) => break,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    /// Error for the next token, without consuming it, when it is not one of `expected`.
+    fn unexpected(&mut self, expected: &[TokenKind]) -> ParseError {
+        match self.input.peek() {
+            Some((token, span)) => ParseError::unexpected(token.clone(), *span, expected),
+            None => ParseError::eof(Span::new(self.end, self.end), expected),
+        }
+    }
+}
+
+impl Iterator for Parser<'_> {
+    type Item = Result<AST, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.pending.take() {
+            return Some(Err(error));
+        }
+
+        // empty statements between consecutive semicolons are skipped
+        while self.next_if(TokenKind::Semicolon).is_some() {}
+        let start = self.input.peek()?.1.start();
+
+        let statement = match self.expect_statement() {
+            Ok(statement) if self.peek().is_none() => Ok(statement),
+            Ok(statement) if self.next_if(TokenKind::Semicolon).is_some() => Ok(statement),
+            // a statement only missing its `;` before the next one is kept, the error follows it
+            Ok(statement)
+                if self
+                    .peek()
+                    .is_some_and(|token| STATEMENTS.contains(&token.kind())) =>
+            {
+                self.pending = Some(self.unexpected(&[TokenKind::Semicolon]));
+                Ok(statement)
+            }
+            Ok(_) => Err(self.unexpected(&[TokenKind::Semicolon])),
+            Err(error) => Err(error),
+        };
+        self.last = Span::new(start, self.end);
+        if statement.is_err() {
+            self.synchronize();
+        }
+
+        Some(statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(source: &str) -> FilterExpression {
+        let statement = format!("select * from book where {source};");
+        match Parser::new(Lexer::new(&statement)).parse() {
+            Ok(AST::Select(query)) if query.filter.is_some() => query.filter.unwrap(),
+            other => panic!("`{statement}` did not parse to a filtered select: {other:?}"),
+        }
+    }
+
+    fn equals(property: &str, value: i64) -> FilterExpression {
+        FilterExpression::Comparison(
+            Expression::Property(property.to_string()),
+            Operator::Equals,
+            Expression::Literal(Value::Int(value)),
+        )
+    }
+
+    fn and(lhs: FilterExpression, rhs: FilterExpression) -> FilterExpression {
+        FilterExpression::And(Box::new(lhs), Box::new(rhs))
+    }
+
+    fn or(lhs: FilterExpression, rhs: FilterExpression) -> FilterExpression {
+        FilterExpression::Or(Box::new(lhs), Box::new(rhs))
+    }
+
+    fn not(operand: FilterExpression) -> FilterExpression {
+        FilterExpression::Not(Box::new(operand))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or_on_the_right() {
+        assert_eq!(
+            filter("a = 1 or b = 2 and c = 3"),
+            or(equals("a", 1), and(equals("b", 2), equals("c", 3)))
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or_on_the_left() {
+        assert_eq!(
+            filter("a = 1 and b = 2 or c = 3"),
+            or(and(equals("a", 1), equals("b", 2)), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn and_is_left_associative() {
+        assert_eq!(
+            filter("a = 1 and b = 2 and c = 3"),
+            and(and(equals("a", 1), equals("b", 2)), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn or_is_left_associative() {
+        assert_eq!(
+            filter("a = 1 or b = 2 or c = 3"),
+            or(or(equals("a", 1), equals("b", 2)), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        assert_eq!(
+            filter("(a = 1 or b = 2) and c = 3"),
+            and(or(equals("a", 1), equals("b", 2)), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(
+            filter("not a = 1 and b = 2"),
+            and(not(equals("a", 1)), equals("b", 2))
+        );
+    }
+
+    #[test]
+    fn not_applies_to_a_parenthesised_group() {
+        assert_eq!(
+            filter("not (a = 1 or b = 2)"),
+            not(or(equals("a", 1), equals("b", 2)))
+        );
+    }
+
+    #[test]
+    fn not_nests_and_accepts_bang() {
+        assert_eq!(filter("not ! a = 1"), not(not(equals("a", 1))));
+    }
+
+    #[test]
+    fn missing_operand_is_an_error() {
+        let error = Parser::new(Lexer::new("select * from book where a = 1 and;"))
+            .parse()
+            .unwrap_err();
+
+        assert_eq!(error.expected, EXPRESSIONS);
+    }
+
+    fn comparison(source: &str) -> String {
+        match filter(source) {
+            FilterExpression::Comparison(lhs, _, rhs) => format!("{lhs} | {rhs}"),
+            other => panic!("`{source}` is not a single comparison: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn products_bind_tighter_than_sums() {
+        assert_eq!(
+            comparison("a + b * c - d = a * b + c"),
+            "a + b * c - d | a * b + c"
+        );
+        assert_eq!(
+            comparison("(a + b) * c = a - (b - c)"),
+            "(a + b) * c | a - (b - c)"
+        );
+        assert_eq!(comparison("-(a + 1) = -a * 2"), "-(a + 1) | -a * 2");
+    }
+
+    #[test]
+    fn parenthesised_values_start_comparisons() {
+        assert_eq!(comparison("(pages + 1) * 2 > 10"), "(pages + 1) * 2 | 10");
+        assert_eq!(comparison("((a)) = lower(b)"), "a | lower(b)");
+        assert_eq!(
+            filter("(a = 1 or (b) = 2) and c = 3"),
+            and(or(equals("a", 1), equals("b", 2)), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn value_without_comparison_is_rejected() {
+        let error = Parser::new(Lexer::new("select * from book where (a + 1);"))
+            .parse()
+            .unwrap_err();
+
+        assert_eq!(error.expected, OPERATORS);
+        assert_eq!(
+            error.message,
+            "expected `=`, `!=`, `<`, `>`, `<=`, `>=`, `like`, `ilike`, `in`, `not in`, `contains`, \
+             `starts with`, `ends with`, `matches` or `is`, found `;`"
+        );
+    }
+
+    #[test]
+    fn literals_nest_in_created_values() {
+        let statement =
+            r#"create book { tags = ["ya", -1, 2.5e3, true, null], meta = { a = { b = [] } } };"#;
+        let Ok(AST::Create(_, values)) = Parser::new(Lexer::new(statement)).parse() else {
+            panic!("`{statement}` did not parse to a create");
+        };
+
+        assert_eq!(
+            values["tags"].to_string(),
+            r#"["ya", -1, 2500.0, true, null]"#
+        );
+        assert_eq!(values["meta"].to_string(), "{ a = { b = [] } }");
+    }
+
+    #[test]
+    fn most_negative_int_is_a_literal() {
+        assert_eq!(
+            comparison("a = -9223372036854775808"),
+            "a | -9223372036854775808"
+        );
+
+        let error = Parser::new(Lexer::new("create book { a = 9223372036854775808 };"))
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::IntOutOfRange);
+    }
+
+    #[test]
+    fn query_clauses_are_contextual_keywords() {
+        let statement =
+            "select distinct title from book order by pages desc, title limit 5 offset 10;";
+        let Ok(AST::Select(query)) = Parser::new(Lexer::new(statement)).parse() else {
+            panic!("`{statement}` did not parse to a select");
+        };
+
+        assert!(query.distinct);
+        assert_eq!(
+            query.order,
+            [
+                SortKey {
+                    expression: Expression::Property("pages".to_string()),
+                    direction: Direction::Descending,
+                },
+                SortKey {
+                    expression: Expression::Property("title".to_string()),
+                    direction: Direction::Ascending,
+                },
+            ]
+        );
+        assert_eq!((query.limit, query.offset), (Some(5), Some(10)));
+
+        let statement = "select distinct, limit from book;";
+        let Ok(AST::Select(query)) = Parser::new(Lexer::new(statement)).parse() else {
+            panic!("`{statement}` did not parse to a select");
+        };
+        assert!(!query.distinct);
+        assert!(matches!(query.projection, Projection::Fields(fields) if fields.len() == 2));
+    }
+
+    #[test]
+    fn counts_must_be_ints() {
+        for clause in ["limit 9223372036854775808", "offset 99999999999999999999"] {
+            let statement = format!("select * from book {clause};");
+            let error = Parser::parse_one(&statement).unwrap_err();
+            assert_eq!(error.kind, ParseErrorKind::IntOutOfRange, "{statement}");
+            assert_eq!(error.span.end(), statement.len() - 1);
+        }
+
+        let Ok(AST::Select(query)) =
+            Parser::parse_one("select * from book limit 9223372036854775807")
+        else {
+            panic!("expected a select");
+        };
+        assert_eq!(query.limit, Some(i64::MAX as usize));
+        let error = Parser::parse_one("select * from book limit -1").unwrap_err();
+        assert_eq!(error.expected, [TokenKind::IntLiteral]);
+    }
+
+    #[test]
+    fn aggregates_group_and_filter_groups() {
+        let statement = "select genre, count(*), sum(distinct pages) + 1 from book \
+            group by genre having count(distinct title) > 3;";
+        let Ok(AST::Select(query)) = Parser::new(Lexer::new(statement)).parse() else {
+            panic!("`{statement}` did not parse to a select");
+        };
+
+        let Projection::Fields(fields) = &query.projection else {
+            panic!("expected fields, got {:?}", query.projection);
+        };
+        let fields = fields
+            .iter()
+            .map(|field| field.expression.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["genre", "count(*)", "sum(distinct pages) + 1"]);
+        assert_eq!(query.group, [Expression::Property("genre".to_string())]);
+        assert!(matches!(
+            query.having,
+            Some(FilterExpression::Comparison(
+                Expression::Aggregate(AggregateFunction::Count, true, _),
+                ..
+            ))
+        ));
+    }
+
+    #[test]
+    fn fields_take_aliases_and_dotted_access() {
+        let statement = "select book.title as title, -meta.shelf.row, (a + b).c from author->book;";
+        let Ok(AST::Select(query)) = Parser::new(Lexer::new(statement)).parse() else {
+            panic!("`{statement}` did not parse to a select");
+        };
+
+        let Projection::Fields(fields) = &query.projection else {
+            panic!("expected fields, got {:?}", query.projection);
+        };
+        let access = |target: &str, name: &str| {
+            Expression::Access(
+                Box::new(Expression::Property(target.to_string())),
+                name.to_string(),
+            )
+        };
+        assert_eq!(
+            fields[0],
+            Field {
+                expression: access("book", "title"),
+                alias: Some("title".to_string())
+            }
+        );
+        assert_eq!(
+            fields[1].expression,
+            Expression::Negate(Box::new(Expression::Access(
+                Box::new(access("meta", "shelf")),
+                "row".to_string()
+            )))
+        );
+        assert_eq!(fields[2].expression.to_string(), "(a + b).c");
+    }
+
+    #[test]
+    fn parameters_stand_for_values() {
+        let parameter = |name: &str| Value::Parameter(name.to_string());
+
+        assert_eq!(
+            filter("title like $pattern"),
+            FilterExpression::Comparison(
+                Expression::Property("title".to_string()),
+                Operator::Like,
+                Expression::Literal(parameter("pattern")),
+            )
+        );
+        assert_eq!(
+            filter("tags = [$first, 2]"),
+            FilterExpression::Comparison(
+                Expression::Property("tags".to_string()),
+                Operator::Equals,
+                Expression::Literal(Value::List(vec![parameter("first"), Value::Int(2)])),
+            )
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(filter("a = 1 -- up to the end of the line"), equals("a", 1));
+        assert_eq!(
+            filter("/* a /* nested */ comment */ a /* between */ = 1"),
+            equals("a", 1)
+        );
+        assert_eq!(
+            filter("a = 1 -- and b = 2\n or c = 3"),
+            or(equals("a", 1), equals("c", 3))
+        );
+    }
+
+    #[test]
+    fn keywords_ignore_case_and_quoted_identifiers_are_never_keywords() {
+        assert_eq!(filter("a = 1 OR c = 3"), or(equals("a", 1), equals("c", 3)));
+        assert_eq!(
+            filter("`a` = 1 Or `c` = 3"),
+            or(equals("a", 1), equals("c", 3))
+        );
+        assert_eq!(filter("`from` = 1"), equals("from", 1));
+        assert_eq!(filter("`first name` = 1"), equals("first name", 1));
+        assert_eq!(filter("`back``tick` = 1"), equals("back`tick", 1));
+        assert_eq!(
+            filter("x IS NULL"),
+            FilterExpression::IsNull(Expression::Property("x".to_string()))
+        );
+
+        let Ok(AST::Select(query)) =
+            Parser::new(Lexer::new("SELECT `distinct` FROM `select`")).parse()
+        else {
+            panic!("expected a select");
+        };
+        assert!(!query.distinct);
+        assert!(matches!(query.path.0, EntityDescription::NoId(label) if label == "select"));
+    }
+
+    #[test]
+    fn word_operators_are_contextual() {
+        let operator = |source: &str| match filter(source) {
+            FilterExpression::Comparison(_, operator, _) => operator,
+            other => panic!("`{source}` is not a single comparison: {other:?}"),
+        };
+
+        assert_eq!(operator(r#"genre in ["sf", "ya"]"#), Operator::In);
+        assert_eq!(operator(r#"title ilike "the %""#), Operator::ILike);
+        assert_eq!(operator("genre not in []"), Operator::NotIn);
+        assert_eq!(operator(r#"tags contains "ya""#), Operator::Contains);
+        assert_eq!(operator(r#"title starts with "The""#), Operator::StartsWith);
+        assert_eq!(operator(r#"title ends with "s""#), Operator::EndsWith);
+        assert_eq!(operator(r#"isbn matches "^97[89]""#), Operator::Matches);
+
+        let is_null = FilterExpression::IsNull(Expression::Property("in".to_string()));
+        assert_eq!(filter("in is null"), is_null);
+        assert_eq!(
+            filter("not in is not null and a = 1"),
+            and(
+                not(FilterExpression::IsNotNull(Expression::Property(
+                    "in".to_string()
+                ))),
+                equals("a", 1),
+            )
+        );
+    }
+
+    fn statements(source: &str) -> Vec<Result<AST, ParseError>> {
+        Parser::new(Lexer::new(source)).collect()
+    }
+
+    fn select(label: &str) -> AST {
+        Parser::parse_one(&format!("select * from {label}")).unwrap()
+    }
+
+    #[test]
+    fn statements_are_separated_by_semicolons() {
+        let expected = vec![select("a"), select("b")];
+
+        assert_eq!(
+            Parser::new(Lexer::new("select * from a; select * from b;")).parse_all(),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Parser::new(Lexer::new("select * from a;\nselect * from b")).parse_all(),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn empty_statements_are_skipped() {
+        assert_eq!(
+            Parser::new(Lexer::new(";; select * from a;;\n ; -- none\n;")).parse_all(),
+            Ok(vec![select("a")])
+        );
+        assert_eq!(Parser::new(Lexer::new("")).parse_all(), Ok(Vec::new()));
+        assert_eq!(
+            Parser::new(Lexer::new(" ;;; /* none */ ")).parse_all(),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn missing_semicolon_between_statements_is_reported_after_the_first() {
+        let source = "select * from a select * from b;";
+        let error =
+            ParseError::unexpected(Token::Select, Span::new(16, 22), &[TokenKind::Semicolon]);
+
+        assert_eq!(
+            statements(source),
+            [Ok(select("a")), Err(error.clone()), Ok(select("b"))]
+        );
+        assert_eq!(Parser::new(Lexer::new(source)).parse_all(), Err(error));
+        assert_eq!(
+            Parser::parse_one(source).unwrap_err().span,
+            Span::new(16, 22)
+        );
+    }
+
+    #[test]
+    fn anything_else_after_a_statement_is_an_error() {
+        let statements = statements("create a:x { } 5; select * from b");
+
+        assert_eq!(statements.len(), 2);
+        let error = statements[0].as_ref().unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnexpectedToken(Token::IntLiteral(5))
+        );
+        assert_eq!(error.expected, [TokenKind::Semicolon]);
+        assert_eq!(statements[1], Ok(select("b")));
+    }
+
+    #[test]
+    fn recovery_reports_every_broken_statement_and_keeps_the_others() {
+        let source =
+            "select * from a;\nselect from b;\ncreate a:x { };\nlink a:x -> ;\nselect * from c";
+        let (statements, errors) = Parser::new(Lexer::new(source)).parse_recovering();
+
+        let texts = statements
+            .iter()
+            .map(|(_, span)| &source[span.start()..span.end()])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            ["select * from a;", "create a:x { };", "select * from c"]
+        );
+        assert_eq!(statements[0].0, select("a"));
+        assert_eq!(statements[2].0, select("c"));
+
+        let found = errors
+            .iter()
+            .map(|error| &source[error.span.start()..error.span.end()])
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["from", ";"]);
+        assert_eq!(errors[0].span.start(), source.find("from b").unwrap());
+    }
+
+    #[test]
+    fn recovery_resumes_at_the_next_statement_keyword() {
+        // neither broken statement has its `;`, the next one starts at its keyword
+        let source = "select * from a where\ncreate a:x { } select * from b limit\nbegin";
+        let (statements, errors) = Parser::new(Lexer::new(source)).parse_recovering();
+
+        assert_eq!(
+            statements
+                .into_iter()
+                .map(|(ast, _)| ast)
+                .collect::<Vec<_>>(),
+            [Parser::parse_one("create a:x { }").unwrap(), AST::Begin,]
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].span, Span::new(22, 28));
+        assert_eq!(errors[1].expected, [TokenKind::Semicolon]);
+        assert_eq!(errors[2].span.start(), source.find("begin").unwrap());
+    }
+
+    /// Deterministic source of random statements, each as likely to be awkward to print as not.
+    struct Generator(u64);
+
+    impl Generator {
+        const NAMES: &[&str] = &[
+            "a",
+            "title",
+            "x1",
+            "first_name",
+            "Title",
+            "from",
+            "Select",
+            "in",
+            "is",
+            "desc",
+            "group",
+            "distinct",
+            "count",
+            "constraint",
+            "first name",
+            "back`tick",
+            "",
+            "5th",
+            "é",
+        ];
+        const STRINGS: &[&str] = &[
+            "",
+            "plain",
+            "with \"quotes\"",
+            "back\\slash",
+            "line\nbreak\ttab\r",
+            "-- not /* a */ comment",
+        ];
+        const FLOATS: &[f64] = &[
+            0.0,
+            1.5,
+            -2.25,
+            1e21,
+            1e-7,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+            items[self.below(items.len())].clone()
+        }
+
+        fn name(&mut self) -> String {
+            self.pick(Self::NAMES).to_string()
+        }
+
+        fn entity(&mut self) -> EntityDescription {
+            match self.below(2) {
+                0 => EntityDescription::NoId(self.name()),
+                _ => EntityDescription::WithId(self.name(), self.name()),
+            }
+        }
+
+        fn value(&mut self, depth: usize) -> Value {
+            match self.below(if depth == 0 { 6 } else { 8 }) {
+                0 => Value::String(self.pick(Self::STRINGS).to_string()),
+                1 => Value::Int(self.pick(&[0, 1, -1, 42, i64::MAX, i64::MIN])),
+                2 => Value::Float(self.pick(Self::FLOATS)),
+                3 => Value::Bool(self.below(2) == 0),
+                4 => Value::Null,
+                5 => Value::Parameter(self.pick(&["p", "_q", "name2"]).to_string()),
+                6 => Value::List((0..self.below(3)).map(|_| self.value(depth - 1)).collect()),
+                _ => Value::Map(
+                    (0..self.below(3))
+                        .map(|_| (self.name(), self.value(depth - 1)))
+                        .collect(),
+                ),
+            }
+        }
+
+        /// Maps are left out of expressions, where `{` does not start a value.
+        fn literal(&mut self, depth: usize) -> Value {
+            loop {
+                match self.value(depth) {
+                    Value::Map(_) => continue,
+                    value => return value,
+                }
+            }
+        }
+
+        fn expression(&mut self, depth: usize) -> Expression {
+            let boxed = |generator: &mut Self| Box::new(generator.expression(depth - 1));
+            match self.below(if depth == 0 { 2 } else { 7 }) {
+                0 => Expression::Literal(self.literal(depth.min(1))),
+                1 => Expression::Property(self.name()),
+                2 => Expression::Negate(boxed(self)),
+                3 => {
+                    let operator = self.pick(&[
+                        ArithmeticOperator::Add,
+                        ArithmeticOperator::Subtract,
+                        ArithmeticOperator::Multiply,
+                        ArithmeticOperator::Divide,
+                        ArithmeticOperator::Remainder,
+                    ]);
+                    Expression::Arithmetic(boxed(self), operator, boxed(self))
+                }
+                4 => {
+                    let name = self.pick(&["lower", "len", "my fn"]).to_string();
+                    Expression::Call(
+                        name,
+                        (0..self.below(3))
+                            .map(|_| self.expression(depth - 1))
+                            .collect(),
+                    )
+                }
+                5 => Expression::Access(boxed(self), self.name()),
+                _ => {
+                    let function = self.pick(&[
+                        AggregateFunction::Count,
+                        AggregateFunction::Sum,
+                        AggregateFunction::Avg,
+                        AggregateFunction::Min,
+                        AggregateFunction::Max,
+                        AggregateFunction::Collect,
+                    ]);
+                    let distinct = self.below(2) == 0;
+                    match function == AggregateFunction::Count && !distinct && self.below(2) == 0 {
+                        true => Expression::Aggregate(function, false, None),
+                        false => Expression::Aggregate(function, distinct, Some(boxed(self))),
+                    }
+                }
+            }
+        }
+
+        fn filter(&mut self, depth: usize) -> FilterExpression {
+            let boxed = |generator: &mut Self| Box::new(generator.filter(depth - 1));
+            match self.below(if depth == 0 { 3 } else { 6 }) {
+                0 => {
+                    let operator = self.pick(&[
+                        Operator::Equals,
+                        Operator::NotEquals,
+                        Operator::SmallerThan,
+                        Operator::GreaterThan,
+                        Operator::SmallerThanOrEqual,
+                        Operator::GreaterThanOrEqual,
+                        Operator::Like,
+                        Operator::ILike,
+                        Operator::In,
+                        Operator::NotIn,
+                        Operator::Contains,
+                        Operator::StartsWith,
+                        Operator::EndsWith,
+                        Operator::Matches,
+                    ]);
+                    FilterExpression::Comparison(
+                        self.expression(depth),
+                        operator,
+                        self.expression(depth),
+                    )
+                }
+                1 => FilterExpression::IsNull(self.expression(depth)),
+                2 => FilterExpression::IsNotNull(self.expression(depth)),
+                3 => FilterExpression::Not(boxed(self)),
+                4 => FilterExpression::And(boxed(self), boxed(self)),
+                _ => FilterExpression::Or(boxed(self), boxed(self)),
+            }
+        }
+
+        fn query(&mut self) -> Query {
+            let some = |generator: &mut Self| generator.below(2) == 0;
+            Query {
+                distinct: some(self),
+                projection: match self.below(4) {
+                    0 => Projection::All,
+                    n => Projection::Fields(
+                        (0..n)
+                            .map(|_| Field {
+                                expression: self.expression(2),
+                                alias: some(self).then(|| self.name()),
+                            })
+                            .collect(),
+                    ),
+                },
+                path: PathExpression(
+                    self.entity(),
+                    (0..self.below(3)).map(|_| self.entity()).collect(),
+                ),
+                filter: some(self).then(|| self.filter(3)),
+                group: (0..self.below(3)).map(|_| self.expression(2)).collect(),
+                having: some(self).then(|| self.filter(2)),
+                order: (0..self.below(3))
+                    .map(|_| SortKey {
+                        expression: self.expression(2),
+                        direction: self.pick(&[Direction::Ascending, Direction::Descending]),
+                    })
+                    .collect(),
+                limit: some(self).then(|| self.below(100)),
+                offset: some(self).then(|| self.below(100)),
+            }
+        }
+
+        fn statement(&mut self) -> AST {
+            match self.below(9) {
+                0..=2 => AST::Select(Box::new(self.query())),
+                3 => AST::Create(
+                    self.entity(),
+                    (0..self.below(4))
+                        .map(|_| (self.name(), self.value(2)))
+                        .collect(),
+                ),
+                4 => AST::Link(
+                    self.entity(),
+                    (self.below(2) == 0).then(|| self.name()),
+                    self.entity(),
+                ),
+                5 => {
+                    let types = [
+                        PropertyType::String,
+                        PropertyType::Int,
+                        PropertyType::Float,
+                        PropertyType::Bool,
+                        PropertyType::List,
+                        PropertyType::Map,
+                    ];
+                    let properties = (0..self.below(3))
+                        .map(|_| {
+                            PropertyDefinition::new(
+                                self.name(),
+                                self.pick(&types),
+                                self.below(2) == 0,
+                            )
+                        })
+                        .collect();
+                    AST::DefineLabel(self.name(), properties)
+                }
+                6 => AST::DefineEdge(self.name(), self.name(), self.name()),
+                7 => AST::CreateConstraint(match self.below(2) {
+                    0 => Constraint::Unique {
+                        label: self.name(),
+                        property: self.name(),
+                    },
+                    _ => Constraint::Exists {
+                        label: self.name(),
+                        property: self.name(),
+                    },
+                }),
+                _ => self.pick(&[AST::Begin, AST::Commit, AST::Rollback]),
+            }
+        }
+    }
+
+    #[test]
+    fn printed_statements_parse_back_to_themselves() {
+        let mut generator = Generator(0x2545_f491_4f6c_dd1d);
+        for _ in 0..5000 {
+            let ast = generator.statement();
+            let printed = ast.to_string();
+            assert_eq!(
+                Parser::parse_one(&printed).as_ref(),
+                Ok(&ast),
+                "printed as `{printed}`"
+            );
         }
     }
 }
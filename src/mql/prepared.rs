@@ -0,0 +1,255 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::graph;
+use crate::mql::executor::ExecutionError;
+use crate::mql::parser::{
+    AST, Expression, FilterExpression, Operator, ParseError, Parser, Projection, Value,
+};
+
+/// Values of the `$name` parameters of a statement, by name without the `$`.
+pub type Parameters = HashMap<String, graph::Value>;
+
+/// Statement parsed once, to be executed any number of times with different [`Parameters`].
+///
+/// Parameters stand wherever a value may be written, so values given for them are never read
+/// as MQL. A parameter compared with `like`, `ilike`, `starts with`, `ends with` or `matches`
+/// must be a string, one right of `in` or `not in` a list, and one on either side of `<`, `>`,
+/// `<=` or `>=` a number or a string.
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    ast: AST,
+    parameters: BTreeSet<String>,
+}
+
+impl Prepared {
+    /// Parses `statement`, which must hold exactly one statement.
+    pub fn new(statement: &str) -> Result<Self, ParseError> {
+        Ok(Self::from(Parser::parse_one(statement)?))
+    }
+
+    pub fn ast(&self) -> &AST {
+        &self.ast
+    }
+
+    /// Names of the parameters the statement takes, without the `$`.
+    pub fn parameters(&self) -> impl Iterator<Item = &str> {
+        self.parameters.iter().map(String::as_str)
+    }
+
+    /// The statement with every parameter replaced by its value in `parameters`. Values given
+    /// for parameters the statement does not take are ignored.
+    pub fn bind(&self, parameters: &Parameters) -> Result<AST, ExecutionError> {
+        let mut ast = self.ast.clone();
+        // columns taking parameters are named after the statement as written, not after the
+        // values given
+        if let AST::Select(query) = &mut ast
+            && let Projection::Fields(fields) = &mut query.projection
+        {
+            for field in fields.iter_mut().filter(|field| field.alias.is_none()) {
+                let takes_parameters =
+                    visit_expression(&mut field.expression, &mut |value, _| match value {
+                        Value::Parameter(_) => Err(()),
+                        _ => Ok(()),
+                    })
+                    .is_err();
+                if takes_parameters {
+                    field.alias = Some(field.expression.to_string());
+                }
+            }
+        }
+
+        visit_ast(&mut ast, &mut |value, expected| {
+            let Value::Parameter(name) = value else {
+                return Ok(());
+            };
+            let Some(bound) = parameters.get(name) else {
+                return Err(ExecutionError::MissingParameter(name.clone()));
+            };
+            if let Some(expected) = expected
+                && !is_of_type(bound, expected)
+            {
+                return Err(ExecutionError::MistypedParameter {
+                    name: name.clone(),
+                    expected,
+                    found: bound.type_name(),
+                });
+            }
+
+            *value = Value::from(bound.clone());
+            Ok(())
+        })?;
+
+        Ok(ast)
+    }
+}
+
+impl From<AST> for Prepared {
+    fn from(mut ast: AST) -> Self {
+        let mut parameters = BTreeSet::new();
+        visit_ast(&mut ast, &mut |value, _| {
+            if let Value::Parameter(name) = value {
+                parameters.insert(name.clone());
+            }
+            Ok::<_, ()>(())
+        })
+        .expect("collecting never fails");
+
+        Self { ast, parameters }
+    }
+}
+
+/// Calls `visit` with every value written in `ast` and the type its position requires, if any.
+/// Lists and maps are not visited themselves, only the values inside them.
+fn visit_ast<E>(
+    ast: &mut AST,
+    visit: &mut impl FnMut(&mut Value, Option<&'static str>) -> Result<(), E>,
+) -> Result<(), E> {
+    match ast {
+        AST::Select(query) => {
+            let fields = match &mut query.projection {
+                Projection::Fields(fields) => fields.as_mut_slice(),
+                Projection::All => &mut [],
+            };
+            for expression in fields
+                .iter_mut()
+                .map(|field| &mut field.expression)
+                .chain(&mut query.group)
+                .chain(query.order.iter_mut().map(|key| &mut key.expression))
+            {
+                visit_expression(expression, visit)?;
+            }
+            for filter in query.filter.iter_mut().chain(&mut query.having) {
+                visit_filter(filter, visit)?;
+            }
+
+            Ok(())
+        }
+        AST::Create(_, properties) => properties
+            .values_mut()
+            .try_for_each(|value| visit_value(value, None, visit)),
+        AST::Link(..)
+        | AST::DefineLabel(..)
+        | AST::DefineEdge(..)
+        | AST::CreateConstraint(_)
+        | AST::Begin
+        | AST::Commit
+        | AST::Rollback => Ok(()),
+    }
+}
+
+fn visit_filter<E>(
+    filter: &mut FilterExpression,
+    visit: &mut impl FnMut(&mut Value, Option<&'static str>) -> Result<(), E>,
+) -> Result<(), E> {
+    match filter {
+        FilterExpression::Comparison(lhs, operator, rhs) => {
+            let ordered = matches!(
+                operator,
+                Operator::SmallerThan
+                    | Operator::GreaterThan
+                    | Operator::SmallerThanOrEqual
+                    | Operator::GreaterThanOrEqual
+            );
+            match lhs {
+                Expression::Literal(value) if ordered => visit_value(value, Some(ORDERED), visit)?,
+                lhs => visit_expression(lhs, visit)?,
+            }
+
+            let expected = match operator {
+                Operator::Like
+                | Operator::ILike
+                | Operator::StartsWith
+                | Operator::EndsWith
+                | Operator::Matches => Some("string"),
+                Operator::In | Operator::NotIn => Some("list"),
+                _ if ordered => Some(ORDERED),
+                _ => None,
+            };
+            match rhs {
+                Expression::Literal(value) => visit_value(value, expected, visit),
+                rhs => visit_expression(rhs, visit),
+            }
+        }
+        FilterExpression::IsNull(expression) | FilterExpression::IsNotNull(expression) => {
+            visit_expression(expression, visit)
+        }
+        FilterExpression::Not(filter) => visit_filter(filter, visit),
+        FilterExpression::And(lhs, rhs) | FilterExpression::Or(lhs, rhs) => {
+            visit_filter(lhs, visit)?;
+            visit_filter(rhs, visit)
+        }
+    }
+}
+
+fn visit_expression<E>(
+    expression: &mut Expression,
+    visit: &mut impl FnMut(&mut Value, Option<&'static str>) -> Result<(), E>,
+) -> Result<(), E> {
+    match expression {
+        Expression::Literal(value) => visit_value(value, None, visit),
+        Expression::Property(_) | Expression::Aggregate(_, _, None) => Ok(()),
+        Expression::Negate(operand)
+        | Expression::Access(operand, _)
+        | Expression::Aggregate(_, _, Some(operand)) => visit_expression(operand, visit),
+        Expression::Arithmetic(lhs, _, rhs) => {
+            visit_expression(lhs, visit)?;
+            visit_expression(rhs, visit)
+        }
+        Expression::Call(_, arguments) => arguments
+            .iter_mut()
+            .try_for_each(|argument| visit_expression(argument, visit)),
+    }
+}
+
+fn visit_value<E>(
+    value: &mut Value,
+    expected: Option<&'static str>,
+    visit: &mut impl FnMut(&mut Value, Option<&'static str>) -> Result<(), E>,
+) -> Result<(), E> {
+    match value {
+        Value::List(values) => values
+            .iter_mut()
+            .try_for_each(|value| visit_value(value, None, visit)),
+        Value::Map(entries) => entries
+            .values_mut()
+            .try_for_each(|value| visit_value(value, None, visit)),
+        value => visit(value, expected),
+    }
+}
+
+/// Types a parameter ordered with `<`, `>`, `<=` or `>=` may have.
+const ORDERED: &str = "number or string";
+
+/// Whether `value` has one of the types named `expected`, where ints and floats are both
+/// numbers.
+fn is_of_type(value: &graph::Value, expected: &str) -> bool {
+    expected.split(" or ").any(|expected| match value {
+        graph::Value::Int(_) | graph::Value::Float(_) if expected == "number" => true,
+        value => value.type_name() == expected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_leaves_fields_without_parameters_as_written() {
+        let statement = "select title, pages * 2, $x + 1 as y, $x from book where pages > $x";
+        let parameters = Parameters::from([("x".to_string(), graph::Value::Int(5))]);
+        let bound = Prepared::new(statement).unwrap().bind(&parameters).unwrap();
+
+        let AST::Select(query) = bound else {
+            panic!("not a select: {bound:?}");
+        };
+        let Projection::Fields(fields) = query.projection else {
+            panic!("not fields: {:?}", query.projection);
+        };
+        let aliases = fields
+            .iter()
+            .map(|field| field.alias.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(aliases, [None, None, Some("y"), Some("$x")]);
+        assert_eq!(fields[3].expression, Expression::Literal(Value::Int(5)));
+    }
+}
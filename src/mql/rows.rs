@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::graph::Value;
+
+/// Result of a `select`: named columns and one row per matching node.
+///
+/// A cell is `None` where the node does not have the projected property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rows {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<Value>>>,
+}
+
+impl Rows {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Option<Value>>>) -> Self {
+        debug_assert!(rows.iter().all(|row| row.len() == columns.len()));
+        Self { columns, rows }
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &[Vec<Option<Value>>] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Renders the rows as a bordered text table followed by the row count.
+impl fmt::Display for Rows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.columns.is_empty() {
+            return write!(f, "({} rows)", self.rows.len());
+        }
+
+        let cells = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(cell).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut widths = self
+            .columns
+            .iter()
+            .map(|column| column.chars().count())
+            .collect::<Vec<_>>();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let border = widths
+            .iter()
+            .map(|&width| "-".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("+");
+        let border = format!("+{border}+");
+
+        writeln!(f, "{border}")?;
+        write_row(f, &widths, &self.columns)?;
+        writeln!(f, "{border}")?;
+        for row in &cells {
+            write_row(f, &widths, row)?;
+        }
+        if !cells.is_empty() {
+            writeln!(f, "{border}")?;
+        }
+
+        match self.rows.len() {
+            1 => write!(f, "(1 row)"),
+            count => write!(f, "({count} rows)"),
+        }
+    }
+}
+
+fn cell(value: &Option<Value>) -> String {
+    match value {
+        None => String::new(),
+        Some(Value::String(string)) => string.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, widths: &[usize], cells: &[String]) -> fmt::Result {
+    write!(f, "|")?;
+    for (width, cell) in widths.iter().zip(cells) {
+        write!(f, " {cell:<width$} |")?;
+    }
+    writeln!(f)
+}
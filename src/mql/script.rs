@@ -0,0 +1,152 @@
+use crate::mql::diagnostic::Location;
+use crate::mql::lexer::{Lexer, Span, Token};
+
+/// Source text holding any number of `;`-terminated statements, such as an `.mql` file.
+///
+/// Comments between statements are skipped, those inside one are part of its text.
+pub struct Script {
+    source: String,
+}
+
+impl Script {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+        }
+    }
+
+    /// Every statement with its span, including a trailing one that lacks its `;`.
+    pub fn statements(&self) -> impl Iterator<Item = (&str, Span)> {
+        let mut tokens = Lexer::new(&self.source).filter(|(token, _)| !token.is_trivia());
+
+        std::iter::from_fn(move || {
+            // a lone `;` is an empty statement and is skipped
+            let (_, first) = tokens.find(|(token, _)| *token != Token::Semicolon)?;
+            let mut end = first.end();
+            for (token, span) in tokens.by_ref() {
+                end = span.end();
+                if token == Token::Semicolon {
+                    break;
+                }
+            }
+
+            Some((
+                &self.source[first.start()..end],
+                Span::new(first.start(), end),
+            ))
+        })
+    }
+
+    /// Whether the script ends with a `;` outside of any string literal or comment.
+    pub fn is_complete(&self) -> bool {
+        let last = Lexer::new(&self.source)
+            .filter(|(token, _)| !token.is_trivia())
+            .last();
+        matches!(last, Some((Token::Semicolon, _)))
+    }
+
+    /// Whether the script holds nothing but whitespace and comments.
+    pub fn is_blank(&self) -> bool {
+        Lexer::new(&self.source).all(|(token, _)| token.is_trivia())
+    }
+
+    /// Text of the script, which spans point into.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Location of the byte `offset` in the script.
+    pub fn location(&self, offset: usize) -> Location {
+        Location::of(&self.source, offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statements(source: &str) -> Vec<(String, Location)> {
+        let script = Script::new(source);
+        script
+            .statements()
+            .map(|(text, span)| (text.to_string(), script.location(span.start())))
+            .collect()
+    }
+
+    fn at(line: usize, column: usize) -> Location {
+        Location { line, column }
+    }
+
+    #[test]
+    fn statements_are_split_on_semicolons() {
+        assert_eq!(
+            statements("select * from a; select * from b;"),
+            [
+                ("select * from a;".to_string(), at(1, 1)),
+                ("select * from b;".to_string(), at(1, 18)),
+            ]
+        );
+    }
+
+    #[test]
+    fn statements_report_the_line_and_column_they_start_at() {
+        let source = "-- seed\n\ncreate a:x { };\n  select *\n  from a;\r\n\tselect * from b;";
+
+        assert_eq!(
+            statements(source),
+            [
+                ("create a:x { };".to_string(), at(3, 1)),
+                ("select *\n  from a;".to_string(), at(4, 3)),
+                ("select * from b;".to_string(), at(6, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_count_characters_rather_than_bytes() {
+        assert_eq!(
+            statements("create a:x { name = \"Zoë\" }; select * from a;"),
+            [
+                ("create a:x { name = \"Zoë\" };".to_string(), at(1, 1)),
+                ("select * from a;".to_string(), at(1, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn semicolons_in_strings_and_comments_do_not_split() {
+        assert_eq!(
+            statements("create a:x { name = \"a;b\" } /* ; */ -- ;\n; select * from a;"),
+            [
+                (
+                    "create a:x { name = \"a;b\" } /* ; */ -- ;\n;".to_string(),
+                    at(1, 1)
+                ),
+                ("select * from a;".to_string(), at(2, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_statements_are_skipped_and_a_missing_last_semicolon_is_tolerated() {
+        assert_eq!(
+            statements(";; select * from a;;\n;select * from b"),
+            [
+                ("select * from a;".to_string(), at(1, 4)),
+                ("select * from b".to_string(), at(2, 2)),
+            ]
+        );
+        assert!(statements("  -- nothing\n;").is_empty());
+    }
+
+    #[test]
+    fn scripts_know_whether_they_are_complete_or_blank() {
+        assert!(Script::new("select * from a;  -- done").is_complete());
+        assert!(!Script::new("select * from a").is_complete());
+        assert!(!Script::new("select * from a where x = \";").is_complete());
+        assert!(!Script::new("select * from a /* ; */").is_complete());
+
+        assert!(Script::new("  -- only a comment;\n/* ; */").is_blank());
+        assert!(!Script::new(";").is_blank());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use minerva::mql::rows::Rows;
+use minerva::mql::script::Script;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+use crate::database::Database;
+use crate::{OnError, run_script};
+
+const PROMPT: &str = "minerva> ";
+const CONTINUATION: &str = "     ..> ";
+
+const HELP: &str = "\
+Statements end with `;` and may span several lines.
+
+.labels        list labels with their node counts
+.stats         show graph statistics
+.load <file>   execute every statement in an MQL file
+.help          show this message
+.quit          exit the shell";
+
+/// Interactive shell executing MQL statements against a [`Database`].
+pub struct Repl {
+    database: Database,
+}
+
+enum Control {
+    Continue,
+    Quit,
+}
+
+/// Lines typed so far of statements that may span several of them.
+#[derive(Default)]
+struct Input {
+    buffer: String,
+}
+
+impl Input {
+    fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Adds a line, returning everything typed so far once it ends with a `;`. Input holding
+    /// nothing but whitespace and comments is dropped.
+    fn push(&mut self, line: &str) -> Option<Script> {
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        let script = Script::new(&self.buffer);
+        if script.is_blank() {
+            self.buffer.clear();
+            None
+        } else if script.is_complete() {
+            self.buffer.clear();
+            Some(script)
+        } else {
+            None
+        }
+    }
+}
+
+impl Repl {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+
+    /// Reads statements until `.quit` or end of input.
+    pub fn run(&mut self) -> rustyline::Result<()> {
+        let mut editor = DefaultEditor::new()?;
+        let history = history_file();
+        if let Some(history) = &history {
+            // a missing history file just means a first run
+            let _ = editor.load_history(history);
+        }
+
+        let mut input = Input::default();
+        loop {
+            let prompt = if input.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION
+            };
+
+            let line = match editor.readline(prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => {
+                    input.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(error),
+            };
+
+            if input.is_empty() && line.trim_start().starts_with('.') {
+                editor.add_history_entry(line.trim())?;
+                match self.execute_command(line.trim()) {
+                    Control::Continue => continue,
+                    Control::Quit => break,
+                }
+            }
+
+            if let Some(script) = input.push(&line) {
+                editor.add_history_entry(script.source().trim())?;
+                run_script(&mut self.database, &script, None, OnError::Continue);
+            }
+        }
+
+        if let Some(history) = &history {
+            editor.save_history(history)?;
+        }
+
+        Ok(())
+    }
+
+    fn execute_command(&mut self, command: &str) -> Control {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+
+        match name {
+            ".quit" | ".exit" => return Control::Quit,
+            ".help" => println!("{HELP}"),
+            ".labels" => println!("{}", self.labels()),
+            ".stats" => self.print_stats(),
+            ".load" if argument.is_empty() => eprintln!("error: usage: .load <file>"),
+            ".load" => self.load(argument),
+            _ => eprintln!("error: unknown command `{name}`, try `.help`"),
+        }
+
+        Control::Continue
+    }
+
+    /// Labels in use or declared by a schema, with the number of nodes carrying each.
+    fn labels(&self) -> Rows {
+        let graph = self.database.graph();
+        let symbols = graph.symbols();
+
+        let mut counts = BTreeMap::new();
+        for (label, _) in graph.catalog().labels() {
+            counts.entry(symbols.resolve(label)).or_insert(0_i64);
+        }
+        for node in graph.nodes() {
+            for &label in node.labels() {
+                *counts.entry(symbols.resolve(label)).or_insert(0) += 1;
+            }
+        }
+
+        let rows = counts
+            .into_iter()
+            .map(|(label, count)| {
+                vec![
+                    Some(minerva::graph::Value::String(label.to_string())),
+                    Some(minerva::graph::Value::Int(count)),
+                ]
+            })
+            .collect();
+
+        Rows::new(vec!["label".to_string(), "nodes".to_string()], rows)
+    }
+
+    fn print_stats(&self) {
+        let graph = self.database.graph();
+
+        println!("nodes:       {}", graph.node_count());
+        println!("edges:       {}", graph.edge_count());
+        println!("schemas:     {}", graph.catalog().labels().count());
+        println!("constraints: {}", graph.constraints().count());
+        println!("symbols:     {}", graph.symbols().len());
+    }
+
+    fn load(&mut self, path: &str) {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("error: cannot read `{path}`: {error}");
+                return;
+            }
+        };
+
+        let summary = run_script(
+            &mut self.database,
+            &Script::new(&source),
+            Some(path),
+            OnError::Continue,
+        );
+        println!(
+            "loaded `{path}`: {} of {} statements succeeded",
+            summary.succeeded,
+            summary.succeeded + summary.failed
+        );
+    }
+}
+
+fn history_file() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".minerva_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(input: &mut Input, lines: &[&str]) -> Vec<Option<String>> {
+        lines
+            .iter()
+            .map(|line| input.push(line).map(|script| script.source().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn statements_run_once_a_line_ends_them() {
+        let mut input = Input::default();
+
+        assert_eq!(
+            feed(
+                &mut input,
+                &["select *", "  from book", "  where pages > 3;"]
+            ),
+            [
+                None,
+                None,
+                Some("select *\n  from book\n  where pages > 3;\n".to_string())
+            ]
+        );
+        assert!(input.is_empty());
+        assert_eq!(
+            feed(&mut input, &["create a:b { };  create a:c { };"]),
+            [Some("create a:b { };  create a:c { };\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn semicolons_in_strings_and_comments_do_not_end_statements() {
+        let mut input = Input::default();
+
+        assert_eq!(
+            feed(
+                &mut input,
+                &[
+                    "create a:b { name = \"x;",
+                    "y\" } -- trailing;",
+                    "/* still; open */ ;"
+                ]
+            ),
+            [
+                None,
+                None,
+                Some(
+                    "create a:b { name = \"x;\ny\" } -- trailing;\n/* still; open */ ;\n"
+                        .to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_input_and_comments_are_dropped() {
+        let mut input = Input::default();
+
+        assert_eq!(
+            feed(&mut input, &["", "   ", "-- a comment;"]),
+            [None, None, None]
+        );
+        assert!(input.is_empty());
+        assert_eq!(feed(&mut input, &["select * from a", "-- ;"]), [None, None]);
+        assert!(!input.is_empty());
+
+        input.clear();
+        assert!(input.is_empty());
+        assert_eq!(
+            feed(&mut input, &["select * from b;"]),
+            [Some("select * from b;\n".to_string())]
+        );
+    }
+}